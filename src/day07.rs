@@ -1,56 +1,680 @@
-static INPUT: &'static str = include_str!("input/day07.txt");
+static INPUT: &str = include_str!("input/day07.txt");
 
-use std::collections::HashMap;
+use std::str::FromStr;
+use std::convert::Infallible;
+use std::fmt::Write;
+use std::ops::RangeBounds;
 
-pub fn silver_and_gold() {
-    let mut dir_stack: Vec<String> = Vec::new();
-    let mut sizes: HashMap<String, usize> = HashMap::new();
+/// Index of a node inside [`FileSystem`]
+type NodeId = usize;
 
-    for line in INPUT.lines() {
-        if line.starts_with("$ cd") {
-            let dest_dir = &line[5..];
+#[derive(Debug)]
+enum Kind {
+    Dir { children: Vec<NodeId> },
+    File { size: usize },
+}
 
-            // Handle command
-            match dest_dir {
-                "/" => {
-                    dir_stack = vec!["/".into()] // Reset
-                },
-                ".." => {
-                    dir_stack.pop().unwrap();
-                },
-                _ => {
-                    dir_stack.push(dest_dir.into());
+#[derive(Debug)]
+struct Node {
+    name: String,
+    /// Containing directory, `None` only for the root
+    parent: Option<NodeId>,
+    kind: Kind,
+}
+
+impl Node {
+    fn is_dir(&self) -> bool {
+        matches!(self.kind, Kind::Dir { .. })
+    }
+}
+
+/// Filesystem tree reconstructed from a terminal transcript.
+///
+/// Nodes are stored in an arena and refer to each other by index.
+/// Root directory `/` is always at index 0 and children are always
+/// pushed after their parent, so every parent index is smaller than its children's.
+#[derive(Debug)]
+struct FileSystem {
+    nodes: Vec<Node>,
+}
+
+impl Default for FileSystem {
+    fn default() -> Self {
+        Self {
+            nodes: vec![Node {
+                name: "/".into(),
+                parent: None,
+                kind: Kind::Dir { children: Vec::new() },
+            }],
+        }
+    }
+}
+
+impl FileSystem {
+    const ROOT: NodeId = 0;
+
+    /// Returns child of directory `dir` with given name, if it exists
+    fn child(&self, dir: NodeId, name: &str) -> Option<NodeId> {
+        match &self.nodes[dir].kind {
+            Kind::Dir { children } => children.iter()
+                .copied()
+                .find(|&c| self.nodes[c].name == name),
+            Kind::File { .. } => None,
+        }
+    }
+
+    /// Creates a new node under directory `dir` and returns its index
+    fn insert(&mut self, dir: NodeId, name: &str, kind: Kind) -> NodeId {
+        let id = self.nodes.len();
+        self.nodes.push(Node { name: name.into(), parent: Some(dir), kind });
+
+        match &mut self.nodes[dir].kind {
+            Kind::Dir { children } => children.push(id),
+            Kind::File { .. } => panic!("cannot create '{name}' inside a file"),
+        }
+
+        id
+    }
+
+    /// Absolute path of a node, eg. `/a/e/i`
+    fn path(&self, id: NodeId) -> String {
+        let mut parts = Vec::new();
+        let mut cursor = Some(id);
+        while let Some(c) = cursor {
+            if c != Self::ROOT {
+                parts.push(self.nodes[c].name.as_str());
+            }
+            cursor = self.nodes[c].parent;
+        }
+
+        parts.reverse();
+        format!("/{}", parts.join("/"))
+    }
+
+    /// Total size of every node, indexed by [`NodeId`].
+    /// Directory sizes include everything below them.
+    fn sizes(&self) -> Vec<usize> {
+        let mut sizes: Vec<usize> = self.nodes.iter()
+            .map(|node| match node.kind {
+                Kind::File { size } => size,
+                Kind::Dir { .. } => 0,
+            })
+            .collect();
+
+        // Children always come after their parents,
+        // so walking backwards accumulates sizes bottom-up.
+        for id in (1..self.nodes.len()).rev() {
+            let parent = self.nodes[id].parent.unwrap();
+            sizes[parent] += sizes[id];
+        }
+
+        sizes
+    }
+
+    /// Children of a directory in requested order. Files have no children.
+    fn sorted_children(&self, id: NodeId, sizes: &[usize], sort: SortBy) -> Vec<NodeId> {
+        let mut children = match &self.nodes[id].kind {
+            Kind::Dir { children } => children.clone(),
+            Kind::File { .. } => return Vec::new(),
+        };
+
+        match sort {
+            SortBy::Name => children.sort_by(|&a, &b| self.nodes[a].name.cmp(&self.nodes[b].name)),
+            // Largest first, ties broken by name so output is stable
+            SortBy::Size => children.sort_by(|&a, &b| sizes[b].cmp(&sizes[a])
+                .then_with(|| self.nodes[a].name.cmp(&self.nodes[b].name))),
+        }
+
+        children
+    }
+
+    /// Renders the filesystem like `tree` does, with sizes in parentheses.
+    fn render_tree(&self, sort: SortBy) -> String {
+        fn walk(fs: &FileSystem, id: NodeId, prefix: &str, sizes: &[usize], sort: SortBy, out: &mut String) {
+            let children = fs.sorted_children(id, sizes, sort);
+            for (i, &child) in children.iter().enumerate() {
+                let last = i + 1 == children.len();
+                let node = &fs.nodes[child];
+                let name = if node.is_dir() { format!("{}/", node.name) } else { node.name.clone() };
+
+                writeln!(out, "{prefix}{}{name} ({})", if last { "└── " } else { "├── " }, sizes[child]).unwrap();
+                if node.is_dir() {
+                    let prefix = format!("{prefix}{}", if last { "    " } else { "│   " });
+                    walk(fs, child, &prefix, sizes, sort, out);
                 }
             }
-        } else if line.starts_with("$ ls") {
-            // noise
-        } else {
-            // currently in directory `cur`
-            let (size, _filename) = line.split_once(' ').unwrap();
-            if size == "dir" {
-                continue;
+        }
+
+        let sizes = self.sizes();
+        let mut out = format!("/ ({})\n", sizes[Self::ROOT]);
+        walk(self, Self::ROOT, "", &sizes, sort, &mut out);
+        out
+    }
+
+    /// Renders directory sizes like `du -h --max-depth=N`.
+    /// Subdirectories are listed before their parents, root comes last.
+    fn render_du(&self, max_depth: usize) -> String {
+        fn walk(fs: &FileSystem, id: NodeId, depth: usize, max_depth: usize, sizes: &[usize], out: &mut String) {
+            for child in fs.sorted_children(id, sizes, SortBy::Name) {
+                if fs.nodes[child].is_dir() {
+                    walk(fs, child, depth + 1, max_depth, sizes, out);
+                }
+            }
+
+            if depth <= max_depth {
+                writeln!(out, "{}\t{}", human_size(sizes[id]), fs.path(id)).unwrap();
+            }
+        }
+
+        let sizes = self.sizes();
+        let mut out = String::new();
+        walk(self, Self::ROOT, 0, max_depth, &sizes, &mut out);
+        out
+    }
+
+    /// Iterator over indices of all directories
+    fn dirs(&self) -> impl Iterator<Item = NodeId> + '_ {
+        (0..self.nodes.len()).filter(|&id| self.nodes[id].is_dir())
+    }
+}
+
+/// Disk the transcript was recorded on
+#[derive(Debug, Clone, Copy)]
+pub struct Disk {
+    /// Total capacity of the disk
+    pub capacity: usize,
+    /// How much free space is needed in total
+    pub required: usize,
+}
+
+impl Default for Disk {
+    fn default() -> Self {
+        Self {
+            capacity: 70_000_000,
+            required: 30_000_000,
+        }
+    }
+}
+
+/// Result of [`FileSystem::plan_deletion`]
+#[derive(Debug)]
+struct Plan {
+    /// Directories to delete, none of them is inside another
+    dirs: Vec<NodeId>,
+    /// Total size of deleted directories
+    freed: usize,
+    /// How much had to be freed at minimum
+    needed: usize,
+}
+
+// Queries
+impl FileSystem {
+    /// Directories with total size of at most `threshold`
+    fn small_dirs<'a>(&'a self, sizes: &'a [usize], threshold: usize) -> impl Iterator<Item = NodeId> + 'a {
+        self.dirs().filter(move |&id| sizes[id] <= threshold)
+    }
+
+    /// Files whose size is within `size` and which match glob `pattern`.
+    ///
+    /// Pattern is matched against the file name, or against the absolute path
+    /// if it contains a `/`. See [`glob_match`] for supported syntax.
+    fn find_files(&self, pattern: &str, size: impl RangeBounds<usize>) -> Vec<NodeId> {
+        let full_path = pattern.contains('/');
+
+        (0..self.nodes.len())
+            .filter(|&id| match self.nodes[id].kind {
+                Kind::File { size: s } => size.contains(&s),
+                Kind::Dir { .. } => false,
+            })
+            .filter(|&id| if full_path {
+                glob_match(pattern, &self.path(id))
             } else {
-                let size = size.parse::<usize>().unwrap();
-                for i in 0..dir_stack.len() {
-                    *sizes.entry(dir_stack[..=i].concat()).or_default() += size;
+                glob_match(pattern, &self.nodes[id].name)
+            })
+            .collect()
+    }
+
+    /// `n` largest directories, largest first
+    fn largest_dirs(&self, sizes: &[usize], n: usize) -> Vec<NodeId> {
+        let mut dirs: Vec<NodeId> = self.dirs().collect();
+        dirs.sort_by(|&a, &b| sizes[b].cmp(&sizes[a]).then(a.cmp(&b)));
+        dirs.truncate(n);
+        dirs
+    }
+
+    /// Returns `true` if `ancestor` is `id` itself or any directory above it
+    fn contains(&self, ancestor: NodeId, id: NodeId) -> bool {
+        let mut cursor = Some(id);
+        while let Some(c) = cursor {
+            if c == ancestor {
+                return true;
+            }
+            cursor = self.nodes[c].parent;
+        }
+
+        false
+    }
+
+    /// Finds directories to delete so that `disk` ends up with enough free space.
+    ///
+    /// Considers combinations of up to `max_dirs` directories, none of which contains another,
+    /// and picks the one freeing the least space. Ties are broken by fewer directories.
+    /// With `max_dirs = 1` this is the same as picking the smallest large-enough directory.
+    ///
+    /// Returns `None` if the requirement cannot be met, otherwise the plan
+    /// (which is empty if there already is enough free space).
+    fn plan_deletion(&self, sizes: &[usize], disk: Disk, max_dirs: usize) -> Option<Plan> {
+        let used = sizes[Self::ROOT];
+        let needed = (used + disk.required).saturating_sub(disk.capacity);
+
+        // Largest first, so that the search hits large enough sums early
+        let mut candidates: Vec<NodeId> = self.dirs().collect();
+        candidates.sort_by(|&a, &b| sizes[b].cmp(&sizes[a]).then(a.cmp(&b)));
+
+        struct Search<'a> {
+            fs: &'a FileSystem,
+            sizes: &'a [usize],
+            candidates: Vec<NodeId>,
+            needed: usize,
+            max_dirs: usize,
+            current: Vec<NodeId>,
+            best: Option<(usize, Vec<NodeId>)>,
+        }
+
+        impl Search<'_> {
+            fn is_better(&self, freed: usize) -> bool {
+                match &self.best {
+                    None => true,
+                    Some((best, dirs)) => freed < *best || (freed == *best && self.current.len() < dirs.len()),
+                }
+            }
+
+            fn run(&mut self, start: usize, freed: usize) {
+                if freed >= self.needed {
+                    // Adding more directories would only free more
+                    if self.is_better(freed) {
+                        self.best = Some((freed, self.current.clone()));
+                    }
+                    return;
+                }
+
+                if self.current.len() == self.max_dirs {
+                    return;
+                }
+
+                for i in start..self.candidates.len() {
+                    let dir = self.candidates[i];
+                    let total = freed + self.sizes[dir];
+
+                    // Cannot improve on what we already have
+                    if let Some((best, _)) = self.best {
+                        if total > best {
+                            continue;
+                        }
+                    }
+
+                    // Candidates are sorted by size, so nothing after this reaches the target either
+                    let remaining = self.max_dirs - self.current.len();
+                    if freed + self.sizes[dir] * remaining < self.needed {
+                        break;
+                    }
+
+                    if self.current.iter().any(|&d| self.fs.contains(d, dir) || self.fs.contains(dir, d)) {
+                        continue;
+                    }
+
+                    self.current.push(dir);
+                    self.run(i + 1, total);
+                    self.current.pop();
+                }
+            }
+        }
+
+        let mut search = Search {
+            fs: self,
+            sizes,
+            candidates,
+            needed,
+            max_dirs,
+            current: Vec::new(),
+            best: None,
+        };
+        search.run(0, 0);
+
+        search.best.map(|(freed, dirs)| Plan { dirs, freed, needed })
+    }
+}
+
+/// Matches `name` against a shell-like glob `pattern`.
+/// `*` matches any run of characters (including none), `?` matches a single one.
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+
+    let (mut p, mut n) = (0, 0);
+    // Position of the last `*` and the name position it was tried at
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            },
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            },
+            _ => match backtrack {
+                // Let the last `*` swallow one more character and retry
+                Some((star_p, star_n)) => {
+                    backtrack = Some((star_p, star_n + 1));
+                    p = star_p + 1;
+                    n = star_n + 1;
+                },
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Ordering of directory entries when rendering
+#[derive(Debug, Clone, Copy)]
+pub enum SortBy {
+    Name,
+    Size,
+}
+
+/// Formats a byte count the way `du -h` does, eg. `584`, `9.2K`, `23M`.
+/// Values are rounded up and get a single decimal when below 10.
+fn human_size(bytes: usize) -> String {
+    const UNITS: [&str; 5] = ["K", "M", "G", "T", "P"];
+
+    if bytes < 1024 {
+        return bytes.to_string();
+    }
+
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    loop {
+        // Round first, since rounding up can reach the next unit, eg. 1023.9K is 1.0M
+        let rounded = if value < 10.0 { (value * 10.0).ceil() / 10.0 } else { value.ceil() };
+        if rounded >= 1024.0 && unit + 1 < UNITS.len() {
+            value /= 1024.0;
+            unit += 1;
+            continue;
+        }
+
+        return if rounded < 10.0 {
+            format!("{rounded:.1}{}", UNITS[unit])
+        } else {
+            format!("{rounded}{}", UNITS[unit])
+        };
+    }
+}
+
+/// Inconsistency found while replaying a transcript
+#[derive(Debug, PartialEq, Eq)]
+enum Issue {
+    /// `$ ls` of a directory that has already been listed
+    DuplicateListing { dir: String },
+    /// `$ cd` into a directory that no listing has mentioned
+    UnknownDir { dir: String },
+    /// `$ cd ..` while already at root
+    AboveRoot,
+    /// Command other than `cd` or `ls`
+    UnknownCommand(String),
+    /// Output line that isn't part of any `$ ls`
+    UnexpectedOutput(String),
+    /// Listing entry that isn't `dir <name>` or `<size> <name>`
+    MalformedEntry(String),
+    /// Same file listed again with a different size, first size is kept
+    SizeMismatch { path: String, old: usize, new: usize },
+    /// Name listed as a file in one place and as a directory in another
+    KindMismatch { path: String },
+}
+
+impl std::fmt::Display for Issue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Issue::DuplicateListing { dir } => write!(f, "directory '{dir}' listed more than once"),
+            Issue::UnknownDir { dir } => write!(f, "cd into '{dir}' which hasn't been listed"),
+            Issue::AboveRoot => write!(f, "cd .. above root"),
+            Issue::UnknownCommand(cmd) => write!(f, "unknown command '{cmd}'"),
+            Issue::UnexpectedOutput(line) => write!(f, "output '{line}' outside of ls"),
+            Issue::MalformedEntry(line) => write!(f, "malformed listing entry '{line}'"),
+            Issue::SizeMismatch { path, old, new } => write!(f, "'{path}' listed with size {new}, previously {old}"),
+            Issue::KindMismatch { path } => write!(f, "'{path}' listed as both file and directory"),
+        }
+    }
+}
+
+/// An [`Issue`] together with the 1-based transcript line it was found on
+#[derive(Debug)]
+struct Diagnostic {
+    line: usize,
+    issue: Issue,
+}
+
+impl FileSystem {
+    /// Replays a terminal transcript, recovering from inconsistencies where possible.
+    ///
+    /// - Listing a directory again never double counts, already known entries are kept as is
+    /// - `cd` into an unlisted directory creates it
+    /// - `cd ..` above root stays at root
+    /// - Unknown commands and unparseable lines are skipped
+    ///
+    /// Every recovery is reported as a [`Diagnostic`].
+    fn replay(s: &str) -> (Self, Vec<Diagnostic>) {
+        let mut fs = FileSystem::default();
+        let mut cwd = FileSystem::ROOT;
+        // Directory whose `ls` output is currently being read
+        let mut listing: Option<NodeId> = None;
+        // Which directories have been listed, and which nodes appeared in a listing
+        let mut listed = vec![false];
+        let mut seen = vec![true]; // Root is always known
+
+        let mut diagnostics = Vec::new();
+
+        for (line_i, line) in s.lines().enumerate() {
+            let mut report = |issue| diagnostics.push(Diagnostic { line: line_i + 1, issue });
+
+            if let Some(command) = line.strip_prefix("$ ") {
+                listing = None;
+
+                if let Some(dest_dir) = command.strip_prefix("cd ") {
+                    cwd = match dest_dir {
+                        "/" => FileSystem::ROOT,
+                        ".." => match fs.nodes[cwd].parent {
+                            Some(parent) => parent,
+                            None => {
+                                report(Issue::AboveRoot);
+                                FileSystem::ROOT
+                            },
+                        },
+                        _ => match fs.child(cwd, dest_dir) {
+                            Some(id) if fs.nodes[id].is_dir() => {
+                                if !seen[id] {
+                                    report(Issue::UnknownDir { dir: fs.path(id) });
+                                }
+                                id
+                            },
+                            Some(id) => {
+                                report(Issue::KindMismatch { path: fs.path(id) });
+                                cwd
+                            },
+                            None => {
+                                let id = fs.insert(cwd, dest_dir, Kind::Dir { children: Vec::new() });
+                                listed.push(false);
+                                seen.push(false);
+                                report(Issue::UnknownDir { dir: fs.path(id) });
+                                id
+                            },
+                        },
+                    };
+                } else if command == "ls" {
+                    if listed[cwd] {
+                        report(Issue::DuplicateListing { dir: fs.path(cwd) });
+                    }
+                    listed[cwd] = true;
+                    listing = Some(cwd);
+                } else {
+                    report(Issue::UnknownCommand(command.into()));
                 }
+
+                continue;
+            }
+
+            let Some(dir) = listing else {
+                report(Issue::UnexpectedOutput(line.into()));
+                continue;
+            };
+
+            // Listing entry of directory `dir`
+            let entry = match line.split_once(' ') {
+                Some(("dir", name)) => Some((name, Kind::Dir { children: Vec::new() })),
+                Some((size, name)) => size.parse().ok().map(|size| (name, Kind::File { size })),
+                None => None,
+            };
+            let Some((name, kind)) = entry else {
+                report(Issue::MalformedEntry(line.into()));
+                continue;
+            };
+
+            match fs.child(dir, name) {
+                Some(id) => {
+                    seen[id] = true;
+                    match (&fs.nodes[id].kind, &kind) {
+                        (Kind::File { size: old }, Kind::File { size: new }) if old != new => {
+                            report(Issue::SizeMismatch { path: fs.path(id), old: *old, new: *new });
+                        },
+                        (Kind::File { .. }, Kind::Dir { .. }) | (Kind::Dir { .. }, Kind::File { .. }) => {
+                            report(Issue::KindMismatch { path: fs.path(id) });
+                        },
+                        _ => {}, // Already known, nothing to add
+                    }
+                },
+                None => {
+                    fs.insert(dir, name, kind);
+                    listed.push(false);
+                    seen.push(true);
+                },
             }
         }
+
+        (fs, diagnostics)
+    }
+}
+
+impl FromStr for FileSystem {
+    type Err = Infallible; // Inconsistencies are recovered from, see `FileSystem::replay`
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self::replay(s).0)
     }
+}
 
-    let silver = sizes.iter()
-        .filter(|(_, &size)| size <= 100_000)
-        .fold(0 as usize, |acc, (_, &size)| acc + size);
+/// Replays [`INPUT`], printing any inconsistencies to stderr
+fn load() -> FileSystem {
+    let (fs, diagnostics) = FileSystem::replay(INPUT);
+    for d in &diagnostics {
+        eprintln!("warning: line {}: {}", d.line, d.issue);
+    }
 
-    let used: &usize = sizes.get("/".into()).unwrap(); // Total usage
-    let target: usize = (used + 30_000_000) - 70_000_000; // How much space we need at minimum
+    fs
+}
 
-    let gold = sizes.iter()
-        .filter_map(|(_, &size)| if size >= target { Some(size) } else { None })
-        .min()
-        .unwrap();
+pub fn silver_and_gold() {
+    const SMALL_DIR: usize = 100_000;
+
+    let fs = load();
+    let sizes = fs.sizes();
+
+    let silver: usize = fs.small_dirs(&sizes, SMALL_DIR)
+        .map(|id| sizes[id])
+        .sum();
+
+    // Gold is the smallest single directory that frees up enough space
+    let gold = fs.plan_deletion(&sizes, Disk::default(), 1).unwrap();
 
     println!("silver: {}", silver);
-    println!("gold:   {}", gold);
+    println!("gold:   {}", gold.freed);
+}
+
+/// Prints the reconstructed filesystem like `tree`
+pub fn tree(sort: SortBy) {
+    let fs = load();
+    print!("{}", fs.render_tree(sort));
+}
+
+/// Prints directory sizes like `du -h --max-depth=N`
+pub fn du(max_depth: usize) {
+    let fs = load();
+    print!("{}", fs.render_du(max_depth));
+}
+
+/// Prints files matching glob `pattern` whose size is within `size`
+pub fn find(pattern: &str, size: impl RangeBounds<usize>) {
+    let fs = load();
+    for id in fs.find_files(pattern, size) {
+        if let Kind::File { size } = fs.nodes[id].kind {
+            println!("{size}\t{}", fs.path(id));
+        }
+    }
+}
+
+/// Prints `n` largest directories
+pub fn largest(n: usize) {
+    let fs = load();
+    let sizes = fs.sizes();
+    for id in fs.largest_dirs(&sizes, n) {
+        println!("{}\t{}", sizes[id], fs.path(id));
+    }
+}
+
+/// Prints which directories to delete to get `disk.required` free space,
+/// using combinations of at most `max_dirs` directories
+pub fn plan_deletion(disk: Disk, max_dirs: usize) {
+    let fs = load();
+    let sizes = fs.sizes();
+
+    match fs.plan_deletion(&sizes, disk, max_dirs) {
+        Some(plan) => {
+            println!("Need to free {}, plan frees {}:", plan.needed, plan.freed);
+            for id in plan.dirs {
+                println!("  {}\t{}", sizes[id], fs.path(id));
+            }
+        },
+        None => println!("Cannot free enough space with at most {max_dirs} directories"),
+    }
 }
 
+/// Checks the transcript for inconsistencies and prints each with its line number
+pub fn validate() {
+    let (_, diagnostics) = FileSystem::replay(INPUT);
+    for d in &diagnostics {
+        println!("line {}: {}", d.line, d.issue);
+    }
+
+    println!("{} issue(s) found", diagnostics.len());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn human_size_matches_du() {
+        assert_eq!(human_size(584), "584");
+        assert_eq!(human_size(1023), "1023");
+        assert_eq!(human_size(1024), "1.0K");
+        assert_eq!(human_size(1536), "1.5K");
+        assert_eq!(human_size(10239), "10K");
+        assert_eq!(human_size(1048575), "1.0M");
+        assert_eq!(human_size(1048576), "1.0M");
+        assert_eq!(human_size(1048577), "1.1M");
+        assert_eq!(human_size(24933642), "24M");
+    }
+}
@@ -1,195 +1,328 @@
-use std::sync::Arc;
-use std::{str::FromStr, convert::Infallible, num::NonZeroUsize, sync::atomic::AtomicUsize};
-use itertools::Itertools;
 use std::thread;
-use std::sync::atomic::Ordering;
 
-static INPUT: &'static str = include_str!("input/day08.txt");
+use crate::grid::{self, Grid};
 
-#[derive(Debug, Clone)]
-struct Grid {
-    size: usize,
-    trees: Vec<(u8, bool)>,
-}
+static INPUT: &str = include_str!("input/day08.txt");
 
-impl Grid {
-    fn n(&self, col: usize, row: usize) -> u8 {
-        self.trees[row*self.size + col].0
-    }
+/// Tree heights and whether each tree has been marked visible
+type Forest = Grid<(u8, bool)>;
 
-    fn mark(&mut self, col: usize, row: usize) {
-        self.trees[row*self.size + col].1 = true;
-    }
+/// Parses a rectangular map of tree heights
+fn parse(s: &str) -> Result<Forest, grid::ParseError> {
+    Grid::parse(s, |c| c.to_digit(10).map(|h| (h as u8, false)))
 }
 
-impl FromStr for Grid {
-    type Err = Infallible; // just panic lol
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let len = s.lines().nth(0).unwrap().len(); // Assume NxN grid
-        Ok(Grid {
-            size: len,
-            trees: Vec::from_iter(
-                s.as_bytes()
-                .into_iter()
-                .filter(|c| c.is_ascii_digit()) // Get rid of newlines
-                .map(|&c| (c - b'0', false)) // Convert ASCII number char to actual number quickly
-            )
-        })
-    }
-}
+/// Marks every tree that is visible from outside the forest
+fn mark_visible(g: &mut Forest) {
+    let (width, height) = (g.width(), g.height());
 
-pub fn silver() {
-    let mut g: Grid = INPUT.parse().unwrap();
-
-    // Columnwise north and south
-    for col in 0..g.size {
-        // NORTH
-        let mut highest = g.n(col, 0); // First tree in current column is always highest
-        g.mark(col, 0); // Also always visible
-        for i in 1..g.size-1 {
+    // Walk inwards from every border tree, starting points paired with walking direction
+    let columns = (0..width).flat_map(|col| [
+        ((col, 0), grid::SOUTH),          // NORTH edge
+        ((col, height - 1), grid::NORTH), // SOUTH edge
+    ]);
+    let rows = (0..height).flat_map(|row| [
+        ((0, row), grid::EAST),           // WEST edge
+        ((width - 1, row), grid::WEST),   // EAST edge
+    ]);
+
+    for ((col, row), dir) in columns.chain(rows) {
+        let mut highest = g[(col, row)].0; // First tree in current line is always highest
+        g[(col, row)].1 = true; // Also always visible
+
+        for (c, r) in g.ray(col, row, dir) {
             // Look at next tree,
-            let now = g.n(col, i);
+            let now = g[(c, r)].0;
             // it is visible if now > highest
             if now > highest {
                 highest = now;
-                g.mark(col, i); // mark tree as visible
+                g[(c, r)].1 = true; // mark tree as visible
 
                 if now == 9 {
                     break; // There cannot be anything higher than 9
                 }
             }
         }
+    }
+}
 
-        // SOUTH
-        let mut highest = g.n(col, g.size-1); // First tree in current column is always highest
-        g.mark(col, g.size-1); // Also always visible
-        for i in (1..g.size-1).rev() {
-            // Look at next tree,
-            let now = g.n(col, i);
-            // it is visible if now > highest
-            if now > highest {
-                highest = now;
-                g.mark(col, i); // mark tree as visible
+pub fn silver() {
+    let mut g = parse(INPUT).unwrap();
+    mark_visible(&mut g);
 
-                if now == 9 {
-                    break; // There cannot be anything higher than 9
-                }
-            }
+    let visible_count = g.cells().iter()
+        .filter(|it| it.1)
+        .count();
+    println!("Silver: {}", visible_count);
+}
+
+/// Multiplies `scores` of the trees at `line` by how far each of them can see
+/// looking backwards along the line, ie. towards `line[0]`.
+///
+/// Uses a monotonic stack of trees that haven't been blocked yet,
+/// so each tree is pushed and popped at most once.
+fn sweep(g: &Forest, line: impl Iterator<Item = (usize, usize)>, scores: &mut Grid<usize>, stack: &mut Vec<(usize, u8)>) {
+    stack.clear();
+
+    for (i, p) in line.enumerate() {
+        let height = g[p].0;
+
+        // Lower trees can't block the view of anything after this one
+        while stack.last().is_some_and(|&(_, h)| h < height) {
+            stack.pop();
         }
+
+        // Sees until the first tree at least as high, or the edge
+        let distance = match stack.last() {
+            Some(&(j, _)) => i - j,
+            None => i,
+        };
+        scores[p] *= distance;
+
+        stack.push((i, height));
     }
+}
 
-    // Rowwise west and east
-    for row in 0..g.size {
-        // WEST
-        let mut highest = g.n(0, row); // First tree in current column is always highest
-        g.mark(0, row); // Also always visible
-        for i in 1..g.size-1 {
-            // Look at next tree,
-            let now = g.n(i, row);
-            // it is visible if now > highest
-            if now > highest {
-                highest = now;
-                g.mark(i, row); // mark tree as visible
+/// Scenic score of every tree, computed in O(width * height)
+fn scenic_scores(g: &Forest) -> Grid<usize> {
+    let (width, height) = (g.width(), g.height());
+    let mut scores = Grid::filled(width, height, 1);
+    let mut stack = Vec::with_capacity(width.max(height));
 
-                if now == 9 {
-                    break; // There cannot be anything higher than 9
-                }
-            }
+    for row in 0..height {
+        sweep(g, (0..width).map(|col| (col, row)), &mut scores, &mut stack);       // Looking west
+        sweep(g, (0..width).rev().map(|col| (col, row)), &mut scores, &mut stack); // Looking east
+    }
+
+    for col in 0..width {
+        sweep(g, (0..height).map(|row| (col, row)), &mut scores, &mut stack);       // Looking north
+        sweep(g, (0..height).rev().map(|row| (col, row)), &mut scores, &mut stack); // Looking south
+    }
+
+    scores
+}
+
+/// Highest scenic score and coordinate of the tree having it
+fn best_spot(scores: &Grid<usize>) -> (usize, (usize, usize)) {
+    scores.cells().iter()
+        .enumerate()
+        .max_by_key(|&(_, &score)| score)
+        .map(|(i, &score)| (score, scores.coord(i)))
+        .unwrap()
+}
+
+/// Maps `t` in `[0, 1]` to a colour going blue, cyan, green, yellow, red
+fn heat(t: f64) -> (u8, u8, u8) {
+    const STOPS: [(f64, f64, f64); 5] = [
+        (0.0, 0.0, 255.0),
+        (0.0, 255.0, 255.0),
+        (0.0, 255.0, 0.0),
+        (255.0, 255.0, 0.0),
+        (255.0, 0.0, 0.0),
+    ];
+
+    let t = t.clamp(0.0, 1.0) * (STOPS.len() - 1) as f64;
+    let i = (t as usize).min(STOPS.len() - 2);
+    let f = t - i as f64;
+    let (a, b) = (STOPS[i], STOPS[i + 1]);
+
+    let lerp = |x: f64, y: f64| (x + (y - x) * f).round() as u8;
+    (lerp(a.0, b.0), lerp(a.1, b.1), lerp(a.2, b.2))
+}
+
+/// Relative scenic score in `[0, 1]`.
+/// Square root scaled so that smaller scores remain distinguishable next to the maximum.
+fn relative_score(score: usize, max: usize) -> f64 {
+    if max == 0 { 0.0 } else { (score as f64 / max as f64).sqrt() }
+}
+
+/// Tree heights with visible trees highlighted in green and hidden ones dimmed
+fn render_visibility(g: &Forest) -> String {
+    let mut out = String::new();
+    for row in 0..g.height() {
+        for &(height, visible) in g.row(row) {
+            let colour = if visible { "\x1b[1;32m" } else { "\x1b[2;37m" };
+            out.push_str(&format!("{colour}{height}"));
         }
+        out.push_str("\x1b[0m\n");
+    }
 
-        // EAST
-        let mut highest = g.n(g.size-1, row); // First tree in current column is always highest
-        g.mark(g.size-1, row); // Also always visible
-        for i in (1..g.size-1).rev() {
-            // Look at next tree,
-            let now = g.n(i, row);
-            // it is visible if now > highest
-            if now > highest {
-                highest = now;
-                g.mark(i, row); // mark tree as visible
+    out
+}
 
-                if now == 9 {
-                    break; // There cannot be anything higher than 9
-                }
-            }
+/// Tree heights on a background shaded by scenic score, best spot marked with `*`
+fn render_scores(g: &Forest, scores: &Grid<usize>) -> String {
+    let (max, best) = best_spot(scores);
+
+    let mut out = String::new();
+    for row in 0..g.height() {
+        for col in 0..g.width() {
+            let (r, gr, b) = heat(relative_score(scores[(col, row)], max));
+            let c = if (col, row) == best { '*' } else { char::from(b'0' + g[(col, row)].0) };
+            out.push_str(&format!("\x1b[48;2;{r};{gr};{b}m\x1b[30m{c}"));
         }
+        out.push_str("\x1b[0m\n");
     }
 
-    let visible_count = g.trees.iter()
-        .filter(|it| it.1)
-        .count();
-    println!("Silver: {}", visible_count);
+    out
+}
+
+/// Binary PGM of the visibility mask, visible trees are white.
+/// Every tree is drawn as a `scale` x `scale` block.
+fn visibility_pgm(g: &Forest, scale: usize) -> Vec<u8> {
+    let (width, height) = (g.width() * scale, g.height() * scale);
+    let mut out = format!("P5\n{width} {height}\n255\n").into_bytes();
+
+    for y in 0..height {
+        for x in 0..width {
+            let visible = g[(x / scale, y / scale)].1;
+            out.push(if visible { 255 } else { 0 });
+        }
+    }
+
+    out
+}
+
+/// Binary PPM of scenic scores using the same colours as the terminal heatmap.
+/// Every tree is drawn as a `scale` x `scale` block.
+fn scores_ppm(scores: &Grid<usize>, scale: usize) -> Vec<u8> {
+    let (max, _) = best_spot(scores);
+    let (width, height) = (scores.width() * scale, scores.height() * scale);
+    let mut out = format!("P6\n{width} {height}\n255\n").into_bytes();
+
+    for y in 0..height {
+        for x in 0..width {
+            let (r, g, b) = heat(relative_score(scores[(x / scale, y / scale)], max));
+            out.extend([r, g, b]);
+        }
+    }
+
+    out
 }
 
 pub fn gold() {
-    let g: Arc<Grid> = Arc::new(INPUT.parse().unwrap());
-    let max: Arc<AtomicUsize> = Arc::new(AtomicUsize::new(0));
-
-    let stride = thread::available_parallelism()
-        .unwrap_or(unsafe { NonZeroUsize::new_unchecked(1) })
-        .get();
-
-    println!("available parallelism: {stride}");
-    thread::scope(|scope| {
-        for t in 0..stride {
-            let g = g.clone();
-            let max = max.clone();
-
-            scope.spawn(move || {
-                // Pre-allocate vectors for each direction
-                let mut arena: [Vec<u8>; 4] = std::array::from_fn(|_| Vec::with_capacity(g.size));
-
-                for tree in (t+1..g.size*g.size).step_by(stride) {
-                    let col = tree.rem_euclid(g.size);
-                    let row = tree.div_euclid(g.size);
-
-                    // Skip borders
-                    if col == 0 || row == 0 || col == g.size || row == g.size {
-                        continue;
-                    }
-
-                    let height = g.n(col, row);
-
-                    // sad: https://github.com/rust-lang/rust/issues/62208
-                    let mut iter_north = (0..row).rev().map(|i| g.n(col, i)).peekable();
-                    let s_n = iter_north.peeking_take_while(|&h| h < height);
-                    arena[0].extend(s_n);
-                    arena[0].extend(iter_north.next());
-
-                    let mut iter_south = (row+1..g.size).map(|i| g.n(col, i)).peekable();
-                    let s_s = iter_south.peeking_take_while(|&h| h < height);
-                    arena[1].extend(s_s);
-                    arena[1].extend(iter_south.next());
-
-                    let mut iter_east = (0..col).rev().map(|i| g.n(i, row)).peekable();
-                    let s_e = iter_east.peeking_take_while(|&h| h < height);
-                    arena[2].extend(s_e);
-                    arena[2].extend(iter_east.next());
-
-                    let mut iter_west = (col+1..g.size).map(|i| g.n(i, row)).peekable();
-                    let s_w = iter_west.peeking_take_while(|&h| h < height);
-                    arena[3].extend(s_w);
-                    arena[3].extend(iter_west.next());
-
-                    let total = arena.iter()
-                        .map(|v| v.len())
-                        .reduce(|acc, it| acc * it)
-                        .unwrap();
-
-                    // Update maximum value
-                    max.fetch_update(
-                        Ordering::Relaxed,
-                        Ordering::Relaxed,
-                        |m| if total > m { Some(total) } else { None }
-                    ).ok();
-
-                    // Clear arena for reuse
-                    arena.iter_mut().for_each(|vec| vec.clear());
+    let g = parse(INPUT).unwrap();
+    let scores = scenic_scores(&g);
+    let (max, (col, row)) = best_spot(&scores);
+
+    println!("Gold: {max} at ({col}, {row})");
+}
+
+/// Scenic score of a single tree by walking outwards in every direction
+fn scenic_score(g: &Forest, col: usize, row: usize) -> usize {
+    let height = g[(col, row)].0;
+
+    grid::DIRECTIONS4.into_iter()
+        .map(|dir| {
+            let mut distance = 0;
+            for p in g.ray(col, row, dir) {
+                distance += 1;
+                if g[p].0 >= height {
+                    break; // View blocked
                 }
-            });
-        }
+            }
+            distance
+        })
+        .product()
+}
+
+/// Same as [`scenic_scores`] but walks outwards from every tree, spread over `threads` threads.
+///
+/// Rows are split into contiguous chunks, one per thread, so every tree is scored exactly once
+/// and results don't depend on the thread count.
+fn scenic_scores_threaded(g: &Forest, threads: usize) -> Grid<usize> {
+    let (width, height) = (g.width(), g.height());
+    let threads = threads.clamp(1, height.max(1));
+    let rows_per_thread = height.div_ceil(threads);
+
+    let cells: Vec<usize> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads)
+            .map(|t| {
+                let rows = (t * rows_per_thread).min(height)..((t + 1) * rows_per_thread).min(height);
+                scope.spawn(move || {
+                    rows.flat_map(|row| (0..width).map(move |col| scenic_score(g, col, row)))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+
+        // Joining in spawn order keeps rows in order
+        handles.into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect()
     });
 
-    // This is safe since all other references to Arc have been dropped
-    println!("Gold: {}", Arc::try_unwrap(max).unwrap().into_inner());
+    Grid::new(width, height, cells)
+}
+
+/// Same as [`gold`] but walks outwards from every tree, spread over `threads` threads
+pub fn gold_threaded(threads: usize) {
+    let g = parse(INPUT).unwrap();
+    let scores = scenic_scores_threaded(&g, threads);
+    let (max, (col, row)) = best_spot(&scores);
+
+    println!("Gold: {max} at ({col}, {row}) using {threads} threads");
+}
+
+/// Prints visibility mask and scenic score heatmap to the terminal
+pub fn heatmap() {
+    let mut g = parse(INPUT).unwrap();
+    mark_visible(&mut g);
+    let scores = scenic_scores(&g);
+
+    println!("Visible trees:");
+    print!("{}", render_visibility(&g));
+    println!("\nScenic scores:");
+    print!("{}", render_scores(&g, &scores));
+}
+
+/// Writes visibility mask to `{prefix}visible.pgm` and scenic scores to `{prefix}scenic.ppm`,
+/// drawing each tree as a `scale` x `scale` block
+pub fn export(prefix: &str, scale: usize) -> std::io::Result<()> {
+    let mut g = parse(INPUT).unwrap();
+    mark_visible(&mut g);
+    let scores = scenic_scores(&g);
+
+    std::fs::write(format!("{prefix}visible.pgm"), visibility_pgm(&g, scale))?;
+    std::fs::write(format!("{prefix}scenic.ppm"), scores_ppm(&scores, scale))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// More rows than threads used below, so every thread gets at least one row
+    const FOREST: &str = "\
+3037325120
+2551269873
+6533256014
+3354909182
+3539001627
+7145287306
+2983746510
+0192837465
+5647382910
+9081726354
+4455667788
+1357924680";
+
+    const MAX_THREADS: usize = 8;
+
+    #[test]
+    fn threaded_scores_match_sweep() {
+        let g = parse(FOREST).unwrap();
+        assert!(g.height() > MAX_THREADS);
+
+        let reference = scenic_scores(&g);
+        for col in 0..g.width() {
+            for row in 0..g.height() {
+                assert_eq!(reference[(col, row)], scenic_score(&g, col, row), "score differs at ({col}, {row})");
+            }
+        }
+
+        for threads in 1..=MAX_THREADS {
+            assert_eq!(scenic_scores_threaded(&g, threads), reference, "scores differ with {threads} threads");
+        }
+    }
 }
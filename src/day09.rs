@@ -1,21 +1,22 @@
 use std::collections::HashSet;
+use std::str::FromStr;
 
-static INPUT: &'static str = include_str!("input/day09.txt");
+static INPUT: &str = include_str!("input/day09.txt");
 
 #[derive(Debug)]
-struct Rope<const N: usize> {
-    knots: [(i64, i64); N],
+struct Rope {
+    knots: Vec<(i64, i64)>,
 }
 
-impl<const N: usize> Default for Rope<N> {
-    fn default() -> Self {
+impl Rope {
+    /// Creates a rope of `len` knots, all at the origin
+    fn new(len: usize) -> Self {
+        assert!(len > 0, "rope needs at least a head");
         Self {
-           knots: std::array::from_fn(|_| (0, 0))
+            knots: vec![(0, 0); len],
         }
     }
-}
 
-impl<const N: usize> Rope<N> {
     /// Move specific knot of the rope to absolute coordinate
     /// Note: Doesn't fix the rope afterwards
     fn move_abs(&mut self, i: usize, c: (i64, i64)) {
@@ -27,45 +28,372 @@ impl<const N: usize> Rope<N> {
         let pos = self.knots[i];
         self.move_abs(i, (pos.0 + c.0, pos.1 + c.1));
     }
+
+    /// Moves head one step towards `dir` and lets the rest of the knots catch up
+    fn step(&mut self, dir: (i64, i64)) {
+        self.move_delta(0, dir);
+
+        for i in 0..self.knots.len() - 1 { // Loop through each knot
+            // Check if current head has moved too far away from its tail
+            let (dx, dy) = ( // How much head moved
+                self.knots[i].0 - self.knots[i+1].0,
+                self.knots[i].1 - self.knots[i+1].1,
+            );
+            if std::cmp::max(dx.abs(), dy.abs()) > 1 { // Check if we moved to far (Chebyshev distance)
+                // Sign function is helpful here since we can only move one tile at a time
+                let mx = dx.signum();
+                let my = dy.signum();
+
+                self.move_delta(i+1, (mx, my)); // Move tail to catch up
+            } else {
+                break; // Rest of the rope stays put as well
+            }
+        }
+    }
+
+    fn tail(&self) -> (i64, i64) {
+        *self.knots.last().unwrap()
+    }
+}
+
+#[derive(Debug)]
+enum ParseError {
+    /// Line doesn't have both direction and step count
+    Malformed(String),
+    UnknownDirection(String),
+    InvalidSteps(String),
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::Malformed(line) => write!(f, "expected '<direction> <steps>', got '{line}'"),
+            ParseError::UnknownDirection(dir) => write!(f, "unknown direction '{dir}'"),
+            ParseError::InvalidSteps(steps) => write!(f, "invalid step count '{steps}'"),
+        }
+    }
+}
+
+/// Head motion, eg. `R 4` or diagonally `UL 2`
+#[derive(Debug)]
+struct Motion {
+    dir: (i64, i64),
+    steps: u64,
 }
 
-pub fn silver_and_gold<const N: usize>() {
-    let mut rope: Rope<N> = Rope::default();
-    let mut visited: HashSet<(i64, i64)> = HashSet::new();
-    visited.insert((0, 0));
+impl FromStr for Motion {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (dir, steps) = s.split_once(' ')
+            .ok_or_else(|| ParseError::Malformed(s.into()))?;
+
+        let steps = steps.parse()
+            .map_err(|_| ParseError::InvalidSteps(steps.into()))?;
 
-    for line in INPUT.lines() {
-        let (dir, steps) = line.split_once(' ').unwrap();
-        let steps: i64 = steps.parse().unwrap();
         let dir: (i64, i64) = match dir {
-            "R" => ( 1,  0),
-            "L" => (-1,  0),
-            "U" => ( 0,  1),
-            "D" => ( 0, -1),
-            _   => panic!(), // just panic lemao
+            "R"  => ( 1,  0),
+            "L"  => (-1,  0),
+            "U"  => ( 0,  1),
+            "D"  => ( 0, -1),
+            "UR" | "RU" => ( 1,  1),
+            "UL" | "LU" => (-1,  1),
+            "DR" | "RD" => ( 1, -1),
+            "DL" | "LD" => (-1, -1),
+            _ => return Err(ParseError::UnknownDirection(dir.into())),
         };
 
+        Ok(Motion { dir, steps })
+    }
+}
 
-        for _ in 0..steps {
-            for i in 0..N-1 { // Loop through each knot
-                if i == 0 { rope.move_delta(0, dir) } // Head moves only on first iteration
+/// Parses every motion, failing with 1-based line number of the first bad one
+fn parse(s: &str) -> Result<Vec<Motion>, (usize, ParseError)> {
+    s.lines()
+        .enumerate()
+        .map(|(i, line)| line.parse().map_err(|e| (i + 1, e)))
+        .collect()
+}
 
-                // Check if current head has moved too far away from its tail
-                let (dx, dy) = ( // How much head moved
-                    rope.knots[i].0 - rope.knots[i+1].0,
-                    rope.knots[i].1 - rope.knots[i+1].1,
-                );
-                if std::cmp::max(dx.abs(), dy.abs()) > 1 { // Check if we moved to far (Chebyshev distance)
-                    // Sign function is helpful here since we can only move one tile at a time
-                    let mx = dx.signum();
-                    let my = dy.signum();
+/// Area the head moves within. No other knot can ever leave it either,
+/// since knots only ever step towards the previous one.
+#[derive(Debug, Clone, Copy)]
+struct Bounds {
+    min: (i64, i64),
+    max: (i64, i64),
+}
+
+impl Bounds {
+    /// Follows the head through every motion, starting from the origin
+    fn of_head(motions: &[Motion]) -> Self {
+        let mut pos = (0, 0);
+        let mut bounds = Bounds { min: pos, max: pos };
+
+        for motion in motions {
+            // Motions are straight lines so the end point is enough
+            let steps = motion.steps as i64;
+            pos = (pos.0 + motion.dir.0 * steps, pos.1 + motion.dir.1 * steps);
+            bounds.min = (bounds.min.0.min(pos.0), bounds.min.1.min(pos.1));
+            bounds.max = (bounds.max.0.max(pos.0), bounds.max.1.max(pos.1));
+        }
+
+        bounds
+    }
+
+    /// Number of cells within bounds, `None` if it doesn't even fit into `u64`
+    fn area(&self) -> Option<u64> {
+        let width = u64::try_from(self.max.0 as i128 - self.min.0 as i128 + 1).ok()?;
+        let height = u64::try_from(self.max.1 as i128 - self.min.1 as i128 + 1).ok()?;
+        width.checked_mul(height)
+    }
+}
+
+/// Set of visited positions.
+///
+/// Dense bitmap over the bounding box when it's small enough,
+/// otherwise falls back to hashing positions.
+#[derive(Debug, Clone)]
+enum Visited {
+    Dense {
+        bounds: Bounds,
+        width: usize,
+        bits: Vec<u64>,
+        count: usize,
+    },
+    Sparse(HashSet<(i64, i64)>),
+}
+
+impl Visited {
+    /// Bits all bitmaps of a simulation may use together, 32 MiB worth
+    const DENSE_LIMIT: u64 = 1 << 28;
 
-                    rope.move_delta(i+1, (mx, my)); // Move tail to catch up
+    /// Bitmap if the bounding box fits into `budget` bits, hash set otherwise
+    fn new(bounds: Bounds, budget: u64) -> Self {
+        match bounds.area() {
+            Some(area) if area <= budget => Visited::Dense {
+                bounds,
+                width: (bounds.max.0 - bounds.min.0 + 1) as usize,
+                bits: vec![0; (area as usize).div_ceil(64)],
+                count: 0,
+            },
+            _ => Visited::Sparse(HashSet::new()),
+        }
+    }
+
+    /// Bit index of a position inside the bitmap
+    fn bit(bounds: &Bounds, width: usize, p: (i64, i64)) -> usize {
+        debug_assert!(p.0 >= bounds.min.0 && p.0 <= bounds.max.0 && p.1 >= bounds.min.1 && p.1 <= bounds.max.1);
+        (p.1 - bounds.min.1) as usize * width + (p.0 - bounds.min.0) as usize
+    }
+
+    fn insert(&mut self, p: (i64, i64)) {
+        match self {
+            Visited::Dense { bounds, width, bits, count } => {
+                let i = Self::bit(bounds, *width, p);
+                let mask = 1 << (i % 64);
+                if bits[i / 64] & mask == 0 {
+                    bits[i / 64] |= mask;
+                    *count += 1;
                 }
+            },
+            Visited::Sparse(set) => { set.insert(p); },
+        }
+    }
+
+    fn contains(&self, p: (i64, i64)) -> bool {
+        match self {
+            Visited::Dense { bounds, width, bits, .. } => {
+                let inside = p.0 >= bounds.min.0 && p.0 <= bounds.max.0 && p.1 >= bounds.min.1 && p.1 <= bounds.max.1;
+                inside && {
+                    let i = Self::bit(bounds, *width, p);
+                    bits[i / 64] & (1 << (i % 64)) != 0
+                }
+            },
+            Visited::Sparse(set) => set.contains(&p),
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Visited::Dense { count, .. } => *count,
+            Visited::Sparse(set) => set.len(),
+        }
+    }
+
+    /// Visited positions in no particular order
+    fn iter(&self) -> Box<dyn Iterator<Item = (i64, i64)> + '_> {
+        match self {
+            Visited::Dense { bounds, width, bits, .. } => Box::new(
+                bits.iter()
+                    .enumerate()
+                    .filter(|(_, &word)| word != 0)
+                    .flat_map(|(w, &word)| (0..64).filter(move |b| word & (1 << b) != 0).map(move |b| w * 64 + b))
+                    .map(move |i| (bounds.min.0 + (i % width) as i64, bounds.min.1 + (i / width) as i64))
+            ),
+            Visited::Sparse(set) => Box::new(set.iter().copied()),
+        }
+    }
+}
+
+/// Which knots to record visited positions for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Track {
+    Tail,
+    AllKnots,
+}
+
+/// Runs every motion on a rope of `knots` knots.
+///
+/// Returns the final rope and visited positions, either only for the tail
+/// or for each knot starting from the head, depending on `track`.
+fn simulate(motions: &[Motion], knots: usize, track: Track) -> (Rope, Vec<Visited>) {
+    simulate_with_budget(motions, knots, track, Visited::DENSE_LIMIT)
+}
+
+/// Same as [`simulate`], with bitmaps using at most `budget` bits in total
+fn simulate_with_budget(motions: &[Motion], knots: usize, track: Track, budget: u64) -> (Rope, Vec<Visited>) {
+    let mut rope = Rope::new(knots);
+    let tracked = match track {
+        Track::Tail => 1,
+        Track::AllKnots => knots,
+    };
+
+    // First pass finds out how much space the rope can possibly cover,
+    // every tracked knot gets an equal share of the budget
+    let mut origin = Visited::new(Bounds::of_head(motions), budget / tracked as u64);
+    origin.insert((0, 0));
+    let mut visited = vec![origin; tracked];
+
+    for motion in motions {
+        for _ in 0..motion.steps {
+            rope.step(motion.dir);
+
+            match track {
+                Track::Tail => { visited[0].insert(rope.tail()); },
+                Track::AllKnots => {
+                    for (set, &knot) in visited.iter_mut().zip(&rope.knots) {
+                        set.insert(knot);
+                    }
+                },
             }
+        }
+    }
+
+    (rope, visited)
+}
+
+/// Draws `trail` and the rope like the puzzle description does.
+///
+/// `H` is the head, followed by knots `1`, `2`, ... (or `T` for a two knot rope),
+/// `s` marks the origin and `#` positions on the trail. Earlier knots are drawn on top.
+fn render(rope: &Rope, trail: &Visited) -> String {
+    let points = || trail.iter().chain(rope.knots.iter().copied()).chain(std::iter::once((0, 0)));
+    let (min_x, max_x) = points().map(|p| p.0).fold((i64::MAX, i64::MIN), |(lo, hi), x| (lo.min(x), hi.max(x)));
+    let (min_y, max_y) = points().map(|p| p.1).fold((i64::MAX, i64::MIN), |(lo, hi), y| (lo.min(y), hi.max(y)));
+
+    let label = |i: usize| match i {
+        0 => 'H',
+        _ if rope.knots.len() == 2 => 'T',
+        _ => char::from_digit(i as u32 % 36, 36).unwrap(),
+    };
 
-            visited.insert(rope.knots[N-1]);
+    let mut out = String::new();
+    // y grows upwards, so start from the top row
+    for y in (min_y..=max_y).rev() {
+        for x in min_x..=max_x {
+            let c = match rope.knots.iter().position(|&k| k == (x, y)) {
+                Some(i) => label(i),
+                None if (x, y) == (0, 0) => 's',
+                None if trail.contains((x, y)) => '#',
+                None => '.',
+            };
+            out.push(c);
         }
+        out.push('\n');
+    }
+
+    out
+}
+
+/// Parses [`INPUT`], printing the error to stderr if there is one
+fn load() -> Option<Vec<Motion>> {
+    match parse(INPUT) {
+        Ok(motions) => Some(motions),
+        Err((line, e)) => {
+            eprintln!("line {line}: {e}");
+            None
+        },
+    }
+}
+
+/// Simulates rope of `knots` knots and counts unique tail positions
+pub fn silver_and_gold(knots: usize) {
+    let Some(motions) = load() else { return };
+
+    let (_, visited) = simulate(&motions, knots, Track::Tail);
+    println!("Uniq visits: {}", visited[0].len());
+}
+
+/// Simulates rope of `knots` knots and counts unique positions of every knot
+pub fn per_knot(knots: usize) {
+    let Some(motions) = load() else { return };
+
+    let (_, visited) = simulate(&motions, knots, Track::AllKnots);
+    for (i, set) in visited.iter().enumerate() {
+        println!("Knot {i}: {} uniq visits", set.len());
+    }
+}
+
+/// Simulates rope of `knots` knots and draws the tail trail and final rope position
+pub fn render_trail(knots: usize) {
+    let Some(motions) = load() else { return };
+
+    let (rope, visited) = simulate(&motions, knots, Track::Tail);
+    print!("{}", render(&rope, &visited[0]));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MOTIONS: &str = "\
+R 5
+U 8
+L 8
+D 3
+R 17
+D 10
+L 25
+U 20
+UR 4
+DL 3";
+
+    #[test]
+    fn dense_and_sparse_agree() {
+        let motions = parse(MOTIONS).unwrap();
+
+        for track in [Track::Tail, Track::AllKnots] {
+            let (_, dense) = simulate(&motions, 10, track);
+            let (_, sparse) = simulate_with_budget(&motions, 10, track, 0);
+
+            for (d, s) in dense.iter().zip(&sparse) {
+                assert!(matches!(d, Visited::Dense { .. }));
+                assert!(matches!(s, Visited::Sparse(_)));
+                assert_eq!(d.len(), s.len());
+                assert!(d.iter().all(|p| s.contains(p)));
+            }
+        }
+    }
+
+    #[test]
+    fn budget_is_shared_between_knots() {
+        let motions = parse(MOTIONS).unwrap();
+        let area = Bounds::of_head(&motions).area().unwrap();
+
+        // Enough for a single bitmap but not for ten
+        let (_, tail) = simulate_with_budget(&motions, 10, Track::Tail, area);
+        let (_, all) = simulate_with_budget(&motions, 10, Track::AllKnots, area);
+        assert!(matches!(tail[0], Visited::Dense { .. }));
+        assert!(all.iter().all(|v| matches!(v, Visited::Sparse(_))));
     }
-    println!("Uniq visits: {}", visited.len());
 }
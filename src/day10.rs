@@ -1,89 +1,749 @@
-use std::convert::Infallible;
+use std::str::FromStr;
 
-static INPUT: &'static str = include_str!("input/day10.txt");
+use miniserde::json::{self, Number, Value};
+
+use crate::grid::Grid;
+
+static INPUT: &str = include_str!("input/day10.txt");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Register {
+    X,
+    Y,
+}
+
+impl Register {
+    const COUNT: usize = 2;
+}
+
+impl std::fmt::Display for Register {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Register::X => write!(f, "x"),
+            Register::Y => write!(f, "y"),
+        }
+    }
+}
+
+impl FromStr for Register {
+    type Err = DecodeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "x" => Ok(Register::X),
+            "y" => Ok(Register::Y),
+            _ => Err(DecodeError::UnknownRegister(s.into())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Instruction {
+    /// `noop`
+    Noop,
+    /// `addx V` and `addy V`
+    Add(Register, i64),
+    /// `mul R V`, multiplies register `R` by `V`
+    Mul(Register, i64),
+    /// `jmp +N` / `jmp -N`, offset is relative to the jump itself
+    Jmp(i64),
+}
+
+impl Instruction {
+    /// How many cycles each instruction takes to complete
+    fn cycles(&self) -> usize {
+        match self {
+            Instruction::Noop    => 1,
+            Instruction::Add(..) => 2,
+            Instruction::Mul(..) => 4,
+            Instruction::Jmp(_)  => 1,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum DecodeError {
+    UnknownOpcode(String),
+    UnknownRegister(String),
+    MissingOperand,
+    InvalidOperand(String),
+    /// More operands than the instruction takes
+    TrailingOperand(String),
+}
+
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::UnknownOpcode(op) => write!(f, "unknown opcode '{op}'"),
+            DecodeError::UnknownRegister(r) => write!(f, "unknown register '{r}'"),
+            DecodeError::MissingOperand => write!(f, "missing operand"),
+            DecodeError::InvalidOperand(arg) => write!(f, "invalid operand '{arg}'"),
+            DecodeError::TrailingOperand(arg) => write!(f, "unexpected operand '{arg}'"),
+        }
+    }
+}
+
+impl FromStr for Instruction {
+    type Err = DecodeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split_ascii_whitespace();
+        let opcode = parts.next().unwrap_or_default();
+
+        let mut operand = || parts.next().ok_or(DecodeError::MissingOperand);
+        let value = |arg: &str| arg.parse::<i64>().map_err(|_| DecodeError::InvalidOperand(arg.into()));
+
+        let inst = match opcode {
+            "noop" => Instruction::Noop,
+            "addx" => Instruction::Add(Register::X, value(operand()?)?),
+            "addy" => Instruction::Add(Register::Y, value(operand()?)?),
+            "mul"  => Instruction::Mul(operand()?.parse()?, value(operand()?)?),
+            "jmp"  => Instruction::Jmp(value(operand()?)?),
+            _ => return Err(DecodeError::UnknownOpcode(opcode.into())),
+        };
+
+        match parts.next() {
+            Some(arg) => Err(DecodeError::TrailingOperand(arg.into())),
+            None => Ok(inst),
+        }
+    }
+}
+
+/// Decoded program, one instruction per line
+#[derive(Debug)]
+struct Program(Vec<Instruction>);
+
+impl FromStr for Program {
+    /// 1-based line number and what was wrong with it
+    type Err = (usize, DecodeError);
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.lines()
+            .enumerate()
+            .map(|(i, line)| line.parse().map_err(|e| (i + 1, e)))
+            .collect::<Result<_, _>>()
+            .map(Program)
+    }
+}
 
 #[derive(Debug)]
 struct Machine {
+    /// Cycle currently in progress, starting from 1
     cycle: usize,
-    x: i64,
+    registers: [i64; Register::COUNT],
+    /// Index of the next instruction
+    pc: i64,
+    /// Instruction in progress and how many cycles it still takes
+    pending: Option<(Instruction, usize)>,
 }
 
 impl Default for Machine {
     fn default() -> Self {
+        let mut registers = [0; Register::COUNT];
+        registers[Register::X as usize] = 1;
+
         Self {
             cycle: 1,
-            x: 1,
+            registers,
+            pc: 0,
+            pending: None,
         }
     }
 }
 
-#[derive(Debug)]
-enum Instruction {
-    Noop,
-    Addx(i64)
+/// What happened during a single cycle
+#[derive(Debug, Clone, Copy)]
+struct Cycle {
+    cycle: usize,
+    /// Instruction being executed, it may take more cycles still
+    instruction: Instruction,
+    /// X register during the cycle
+    x_during: i64,
+    /// X register after the cycle, differs only when an instruction completed
+    x_after: i64,
 }
 
-impl std::str::FromStr for Instruction {
-    type Err = Infallible;
+impl Machine {
+    fn reg(&self, r: Register) -> i64 {
+        self.registers[r as usize]
+    }
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.split_once(' ') {
-            Some((_inst, arg)) => {
-                // Assume that instruction is always addx
-                Ok(Instruction::Addx(arg.parse().unwrap()))
+    fn x(&self) -> i64 {
+        self.reg(Register::X)
+    }
+
+    /// Next instruction to execute, `None` once program counter has left the program
+    fn fetch(&self, program: &Program) -> Option<Instruction> {
+        usize::try_from(self.pc).ok()
+            .and_then(|pc| program.0.get(pc))
+            .copied()
+    }
+
+    /// Applies effects of a completed instruction
+    fn execute(&mut self, inst: Instruction) {
+        self.pc += 1;
+        match inst {
+            Instruction::Noop => {},
+            // Registers and program counter wrap around like real hardware would
+            Instruction::Add(r, val) => self.registers[r as usize] = self.registers[r as usize].wrapping_add(val),
+            Instruction::Mul(r, val) => self.registers[r as usize] = self.registers[r as usize].wrapping_mul(val),
+            Instruction::Jmp(offset) => self.pc = self.pc.wrapping_add(offset.wrapping_sub(1)),
+        }
+    }
+
+    /// Runs a single cycle. Returns `None` if the machine has halted.
+    fn tick(&mut self, program: &Program) -> Option<Cycle> {
+        let (inst, left) = match self.pending.take() {
+            Some(pending) => pending,
+            None => {
+                let inst = self.fetch(program)?;
+                (inst, inst.cycles())
             },
-            None => Ok(Instruction::Noop),
+        };
+
+        let cycle = self.cycle;
+        let x_during = self.x();
+
+        // Effects are only visible after the instruction's last cycle
+        if left == 1 {
+            self.execute(inst);
+        } else {
+            self.pending = Some((inst, left - 1));
         }
+        self.cycle += 1;
+
+        Some(Cycle { cycle, instruction: inst, x_during, x_after: self.x() })
+    }
+
+    /// Runs until program counter leaves the program, yielding every cycle
+    fn trace<'a>(&'a mut self, program: &'a Program) -> impl Iterator<Item = Cycle> + 'a {
+        std::iter::from_fn(move || self.tick(program))
     }
 }
 
-impl Machine {
-    fn write_if_interesting(&self, out: &mut Vec<i64>) {
-        const POINTS: [i64; 6] = [20, 60, 100, 140, 180, 220];
-        // POINTS array is sorted so binary search can be used.
-        if let Ok(index) = POINTS.binary_search(&(self.cycle as i64)) {
-            out.push(POINTS[index] * self.x);
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Breakpoint {
+    /// Stop before given cycle starts
+    Cycle(usize),
+    /// Stop when register takes given value
+    Register(Register, i64),
+}
+
+impl std::fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Breakpoint::Cycle(n) => write!(f, "cycle {n}"),
+            Breakpoint::Register(r, v) => write!(f, "{r} == {v}"),
         }
     }
+}
 
-    fn draw(&self) {
-        let h_pos = (self.cycle - 1) % 40;
+/// Why execution stopped
+enum Stop {
+    Halted,
+    Break(Breakpoint),
+    /// Requested number of cycles have been run
+    Done,
+}
+
+/// Steps through a program while drawing the CRT alongside
+struct Debugger {
+    program: Program,
+    machine: Machine,
+    crt: Crt,
+    breakpoints: Vec<Breakpoint>,
+}
 
-        if h_pos == 0 {
-            print!("\n");
+impl Debugger {
+    fn new(program: Program) -> Self {
+        Self {
+            program,
+            machine: Machine::default(),
+            crt: Crt::default(),
+            breakpoints: Vec::new(),
         }
-        if (h_pos as i64 - self.x).abs() <= 1 {
-            print!("█"); // Actual real "pixels"
-        } else {
-            print!(" ");
+    }
+
+    /// Runs one cycle, returns breakpoint hit afterwards if any
+    fn tick(&mut self) -> Result<Option<Breakpoint>, Stop> {
+        let before = self.machine.registers;
+        let c = self.machine.tick(&self.program).ok_or(Stop::Halted)?;
+        self.crt.draw(c.cycle, c.x_during);
+
+        let hit = self.breakpoints.iter().copied().find(|&b| match b {
+            Breakpoint::Cycle(n) => self.machine.cycle == n,
+            // Only trigger when the value changes, otherwise we'd be stuck
+            Breakpoint::Register(r, v) => self.machine.reg(r) == v && before[r as usize] != v,
+        });
+        Ok(hit)
+    }
+
+    /// Runs at most `cycles` cycles, stopping early at breakpoints
+    fn run(&mut self, cycles: usize) -> Stop {
+        for _ in 0..cycles {
+            match self.tick() {
+                Ok(Some(b)) => return Stop::Break(b),
+                Ok(None) => {},
+                Err(stop) => return stop,
+            }
+        }
+
+        Stop::Done
+    }
+
+    /// Runs until the instruction in progress has completed
+    fn next(&mut self) -> Stop {
+        loop {
+            match self.tick() {
+                Ok(Some(b)) => return Stop::Break(b),
+                Ok(None) if self.machine.pending.is_none() => return Stop::Done,
+                Ok(None) => {},
+                Err(stop) => return stop,
+            }
+        }
+    }
+
+    fn print_state(&self) {
+        let m = &self.machine;
+        println!("cycle {} | pc {} | x = {} | y = {}", m.cycle, m.pc, m.reg(Register::X), m.reg(Register::Y));
+        match m.pending {
+            Some((inst, left)) => println!("executing {inst:?}, {left} cycle(s) left"),
+            None => match m.fetch(&self.program) {
+                Some(inst) => println!("next {inst:?}"),
+                None => println!("halted"),
+            },
+        }
+    }
+
+    /// Prints CRT row currently being drawn, with the beam and sprite positions below it
+    fn print_crt_line(&self) {
+        let width = self.crt.screen.width();
+        let (beam, row) = self.crt.screen.coord(self.machine.cycle - 1);
+        if row >= self.crt.screen.height() {
+            println!("beam is past the last row");
+            return;
+        }
+
+        let line: String = self.crt.screen.row(row)
+            .enumerate()
+            .map(|(col, &lit)| if col >= beam { '.' } else if lit { '█' } else { ' ' })
+            .collect();
+        let markers: String = (0..width as i64)
+            .map(|col| match col {
+                _ if col == beam as i64 => '^',
+                _ if self.crt.sprite_covers(col as usize, self.machine.x()) => '=',
+                _ => ' ',
+            })
+            .collect();
+
+        println!("row {row}: |{line}|");
+        println!("        {markers}");
+    }
+
+    fn report(&self, stop: Stop) {
+        match stop {
+            Stop::Halted => println!("program halted at cycle {}", self.machine.cycle),
+            Stop::Break(b) => println!("breakpoint: {b}"),
+            Stop::Done => {},
+        }
+        self.print_state();
+    }
+
+    /// Handles a single command, returns `false` when the debugger should exit
+    fn command(&mut self, line: &str) -> bool {
+        let mut parts = line.split_ascii_whitespace();
+        let cmd = parts.next().unwrap_or("step");
+        let args: Vec<&str> = parts.collect();
+
+        match (cmd, args.as_slice()) {
+            ("s" | "step", []) => { let stop = self.run(1); self.report(stop); },
+            ("s" | "step", [n]) => match n.parse() {
+                Ok(n) => { let stop = self.run(n); self.report(stop); },
+                Err(_) => println!("invalid cycle count '{n}'"),
+            },
+            ("n" | "next", []) => { let stop = self.next(); self.report(stop); },
+            ("c" | "continue", []) => { let stop = self.run(usize::MAX); self.report(stop); },
+            ("r" | "run", [n]) => match n.parse::<usize>() {
+                Ok(n) if n >= self.machine.cycle => {
+                    let stop = self.run(n - self.machine.cycle);
+                    self.report(stop);
+                },
+                Ok(_) => println!("already past cycle {n}"),
+                Err(_) => println!("invalid cycle '{n}'"),
+            },
+            ("b" | "break", ["cycle", n]) => match n.parse() {
+                Ok(n) => self.breakpoints.push(Breakpoint::Cycle(n)),
+                Err(_) => println!("invalid cycle '{n}'"),
+            },
+            ("b" | "break", [r, v]) => match (r.parse(), v.parse()) {
+                (Ok(r), Ok(v)) => self.breakpoints.push(Breakpoint::Register(r, v)),
+                _ => println!("usage: break <x|y> <value>"),
+            },
+            ("b" | "break", []) => {
+                for (i, b) in self.breakpoints.iter().enumerate() {
+                    println!("{i}: {b}");
+                }
+            },
+            ("d" | "delete", [i]) => match i.parse::<usize>() {
+                Ok(i) if i < self.breakpoints.len() => { self.breakpoints.remove(i); },
+                _ => println!("no breakpoint '{i}'"),
+            },
+            ("p" | "print", []) => self.print_state(),
+            ("crt", []) => self.print_crt_line(),
+            ("screen", []) => self.crt.print(),
+            ("q" | "quit", []) => return false,
+            ("h" | "help", []) => println!("\
+step [n]          run n cycles (default 1), empty line steps too
+next              finish current instruction
+continue          run until breakpoint or halt
+run <n>           run until cycle n is about to start
+break             list breakpoints
+break cycle <n>   stop before cycle n
+break <x|y> <v>   stop when register becomes v
+delete <i>        remove breakpoint i
+print             show registers
+crt               show CRT row being drawn, ^ is the beam and = the sprite
+screen            show whole CRT
+quit"),
+            _ => println!("unknown command '{line}', try 'help'"),
         }
+
+        true
     }
 }
 
-pub fn silver_and_gold() {
+/// Width and height of a letter in the CRT font, letters are separated by a blank column
+const GLYPH_WIDTH: usize = 4;
+const GLYPH_HEIGHT: usize = 6;
+
+/// Block letters used by the puzzle, `#` being a lit pixel
+const FONT: [(char, [&str; GLYPH_HEIGHT]); 18] = [
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', [".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+    (' ', ["....", "....", "....", "....", "....", "...."]),
+];
+
+/// Reads letters off the top of the screen, unrecognised ones become `?`
+fn ocr(screen: &Grid<bool>) -> String {
+    if screen.height() < GLYPH_HEIGHT {
+        return String::new();
+    }
+
+    let letters = (screen.width() + 1) / (GLYPH_WIDTH + 1);
+    (0..letters)
+        .map(|i| {
+            let left = i * (GLYPH_WIDTH + 1);
+            let matches = |glyph: &[&str; GLYPH_HEIGHT]| glyph.iter()
+                .enumerate()
+                .all(|(row, line)| line.bytes()
+                    .enumerate()
+                    .all(|(col, c)| (c == b'#') == screen[(left + col, row)]));
+
+            FONT.iter()
+                .find(|(_, glyph)| matches(glyph))
+                .map_or('?', |&(c, _)| c)
+        })
+        .collect()
+}
+
+/// Geometry of the CRT, width and height must both be at least 1
+#[derive(Debug, Clone, Copy)]
+pub struct CrtConfig {
+    /// Pixels per row, one pixel is drawn per cycle
+    pub width: usize,
+    pub height: usize,
+    /// How many pixels wide the sprite is
+    pub sprite_width: usize,
+}
+
+impl Default for CrtConfig {
+    fn default() -> Self {
+        Self {
+            width: 40,
+            height: 6,
+            sprite_width: 3,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Crt {
+    /// Lit pixels of the CRT
+    screen: Grid<bool>,
+    sprite_width: usize,
+}
+
+impl Default for Crt {
+    fn default() -> Self {
+        Self::new(CrtConfig::default())
+    }
+}
+
+impl Crt {
+    /// Panics if the screen would have no pixels
+    fn new(config: CrtConfig) -> Self {
+        assert!(config.width > 0 && config.height > 0, "CRT needs at least one pixel, got {}x{}", config.width, config.height);
+        Self {
+            screen: Grid::filled(config.width, config.height, false),
+            sprite_width: config.sprite_width,
+        }
+    }
+
+    /// Returns `true` if sprite positioned at `x` covers column `col`.
+    /// Sprite is centered at `x`, extra pixel of an even width sprite goes right.
+    fn sprite_covers(&self, col: usize, x: i64) -> bool {
+        let left = x - (self.sprite_width as i64 - 1) / 2;
+        let right = left + self.sprite_width as i64 - 1;
+        (left..=right).contains(&(col as i64))
+    }
+
+    /// Draws pixel of the given cycle with sprite positioned at `x`
+    fn draw(&mut self, cycle: usize, x: i64) {
+        let (col, row) = self.screen.coord(cycle - 1);
+        let lit = self.sprite_covers(col, x);
+
+        // Program may run past the last pixel
+        if let Some(pixel) = self.screen.get_mut(col, row) {
+            *pixel = lit;
+        }
+    }
+
+    fn print(&self) {
+        for row in 0..self.screen.height() {
+            let line: String = self.screen.row(row)
+                .map(|&lit| if lit { '█' } else { ' ' }) // Actual real "pixels"
+                .collect();
+            println!("{line}");
+        }
+    }
+
+    /// Renders two pixel rows per line of text using half blocks
+    fn half_blocks(&self) -> String {
+        let mut out = String::new();
+        for row in (0..self.screen.height()).step_by(2) {
+            for col in 0..self.screen.width() {
+                let upper = self.screen[(col, row)];
+                let lower = self.screen.get(col, row + 1).copied().unwrap_or(false);
+                out.push(match (upper, lower) {
+                    (true, true)   => '█',
+                    (true, false)  => '▀',
+                    (false, true)  => '▄',
+                    (false, false) => ' ',
+                });
+            }
+            out.push('\n');
+        }
+
+        out
+    }
+
+    /// Plain PBM (P1), lit pixels are black ink
+    fn to_pbm(&self) -> String {
+        let mut out = format!("P1\n{} {}\n", self.screen.width(), self.screen.height());
+        for row in 0..self.screen.height() {
+            let line: Vec<&str> = self.screen.row(row)
+                .map(|&lit| if lit { "1" } else { "0" })
+                .collect();
+            out.push_str(&line.join(" "));
+            out.push('\n');
+        }
+
+        out
+    }
+
+    /// Plain PGM (P2), lit pixels are white like on the actual screen
+    fn to_pgm(&self) -> String {
+        let mut out = format!("P2\n{} {}\n255\n", self.screen.width(), self.screen.height());
+        for row in 0..self.screen.height() {
+            let line: Vec<&str> = self.screen.row(row)
+                .map(|&lit| if lit { "255" } else { "0" })
+                .collect();
+            out.push_str(&line.join(" "));
+            out.push('\n');
+        }
+
+        out
+    }
+}
+
+/// Decodes [`INPUT`], printing the error to stderr if there is one
+fn load() -> Option<Program> {
+    match INPUT.parse() {
+        Ok(program) => Some(program),
+        Err((line, e)) => {
+            eprintln!("line {line}: {e}");
+            None
+        },
+    }
+}
+
+/// Runs the program, returning sum of signal strengths and the drawn screen
+fn solve(program: &Program, config: CrtConfig) -> (i64, Crt) {
+    const POINTS: [usize; 6] = [20, 60, 100, 140, 180, 220];
+
     let mut machine = Machine::default();
+    let mut crt = Crt::new(config);
     let mut strengths: Vec<i64> = Vec::new();
 
-    print!("█"); // getting started :^)
-    for line in INPUT.lines() {
-        let inst: Instruction = line.parse().unwrap();
+    // Jumps can loop forever, so stop once every strength and pixel is known
+    let limit = POINTS[POINTS.len() - 1].max(config.width * config.height);
 
-        machine.cycle += 1;
-        machine.draw();
-        match inst {
-            Instruction::Noop => {},
-            Instruction::Addx(val) => {
-                // Do nothing
-                machine.write_if_interesting(&mut strengths);
-
-                // Take extra cycle to increment X
-                machine.cycle += 1;
-                machine.x += val;
-                machine.draw();
+    for c in machine.trace(program).take_while(|c| c.cycle <= limit) {
+        // POINTS array is sorted so binary search can be used.
+        if POINTS.binary_search(&c.cycle).is_ok() {
+            strengths.push(c.cycle as i64 * c.x_during);
+        }
+        crt.draw(c.cycle, c.x_during);
+    }
+
+    (strengths.iter().sum(), crt)
+}
+
+pub fn silver_and_gold() {
+    let Some(program) = load() else { return };
+    let (silver, crt) = solve(&program, CrtConfig::default());
+
+    crt.print();
+    println!("Silver: {silver}");
+    println!("Gold:   {}", ocr(&crt.screen));
+}
+
+/// Prints both answers as JSON, eg. `{"gold":"EHZFZHCZ","silver":13140}`
+pub fn answer_json() {
+    let Some(program) = load() else { return };
+    let (silver, crt) = solve(&program, CrtConfig::default());
+
+    let mut answer = json::Object::new();
+    answer.insert("silver".into(), Value::Number(Number::I64(silver)));
+    answer.insert("gold".into(), Value::String(ocr(&crt.screen)));
+    println!("{}", json::to_string(&answer));
+}
+
+/// Runs the program on a CRT of given geometry and prints the screen using half blocks
+pub fn render(config: CrtConfig) {
+    let Some(program) = load() else { return };
+    let (_, crt) = solve(&program, config);
+
+    print!("{}", crt.half_blocks());
+}
+
+/// Runs the program on a CRT of given geometry and writes the screen
+/// to `{prefix}crt.pbm` and `{prefix}crt.pgm`. Fails with [`std::io::ErrorKind::InvalidData`]
+/// if the program can't be decoded.
+pub fn export(config: CrtConfig, prefix: &str) -> std::io::Result<()> {
+    let program: Program = INPUT.parse()
+        .map_err(|(line, e)| std::io::Error::new(std::io::ErrorKind::InvalidData, format!("line {line}: {e}")))?;
+    let (_, crt) = solve(&program, config);
+
+    std::fs::write(format!("{prefix}crt.pbm"), crt.to_pbm())?;
+    std::fs::write(format!("{prefix}crt.pgm"), crt.to_pgm())?;
+    Ok(())
+}
+
+/// Prints every cycle of the program, stopping after `max_cycles` cycles in case it never halts
+pub fn trace(max_cycles: usize) {
+    let Some(program) = load() else { return };
+
+    println!("cycle | instruction      | X during | X after");
+    for c in Machine::default().trace(&program).take(max_cycles) {
+        let inst = format!("{:?}", c.instruction);
+        println!("{:>5} | {inst:<16} | {:>8} | {:>7}", c.cycle, c.x_during, c.x_after);
+    }
+}
+
+/// Interactive debugger over [`INPUT`], reads commands from stdin. Type `help` for commands.
+pub fn debug() {
+    use std::io::{BufRead, Write};
+
+    let Some(program) = load() else { return };
+    let mut debugger = Debugger::new(program);
+
+    let stdin = std::io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        print!("(dbg) ");
+        std::io::stdout().flush().unwrap();
+
+        let Some(Ok(line)) = lines.next() else { break };
+        if !debugger.command(line.trim()) {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arithmetic_wraps() {
+        let program: Program = "addx 9223372036854775807\naddx 5\njmp -9223372036854775808".parse().unwrap();
+        let mut machine = Machine::default();
+        let last = machine.trace(&program).last().unwrap();
+
+        assert_eq!(last.x_after, 1i64.wrapping_add(i64::MAX).wrapping_add(5));
+        assert_eq!(machine.x(), last.x_after);
+    }
+
+    /// Draws `text` with [`FONT`], one blank column between letters like on the CRT
+    fn draw_text(text: &str) -> Grid<bool> {
+        let width = text.len() * (GLYPH_WIDTH + 1) - 1;
+        let mut screen = Grid::filled(width, GLYPH_HEIGHT, false);
+
+        for (i, c) in text.chars().enumerate() {
+            let (_, glyph) = FONT.iter().find(|&&(g, _)| g == c).unwrap();
+            for (row, line) in glyph.iter().enumerate() {
+                for (col, pixel) in line.bytes().enumerate() {
+                    screen[(i * (GLYPH_WIDTH + 1) + col, row)] = pixel == b'#';
+                }
             }
         }
-        machine.write_if_interesting(&mut strengths); // Combined check for cycles ending NOOP and ADDX
+
+        screen
+    }
+
+    #[test]
+    fn ocr_reads_every_glyph() {
+        let text: String = FONT.iter().map(|&(c, _)| c).collect();
+        assert_eq!(ocr(&draw_text(&text)), text);
     }
 
-    println!("\nSilver: {:?}", strengths.iter().sum::<i64>());
+    #[test]
+    fn ocr_marks_unknown_glyphs() {
+        let mut screen = draw_text("AB");
+        screen[(0, 0)] = true; // `A` has its top left corner unlit
+        assert_eq!(ocr(&screen), "?B");
+    }
+
+    #[test]
+    #[should_panic(expected = "CRT needs at least one pixel")]
+    fn crt_rejects_zero_width() {
+        Crt::new(CrtConfig { width: 0, ..CrtConfig::default() });
+    }
+
+    #[test]
+    fn solve_stops_looping_programs() {
+        let program: Program = "noop\njmp -1".parse().unwrap();
+        let (silver, crt) = solve(&program, CrtConfig::default());
+
+        // X stays at 1 for every cycle
+        assert_eq!(silver, 20 + 60 + 100 + 140 + 180 + 220);
+        // Only the first three columns are ever lit
+        assert_eq!(ocr(&crt.screen), "?       ");
+    }
 }
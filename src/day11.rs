@@ -1,91 +1,886 @@
 use std::collections::VecDeque;
+use std::str::FromStr;
+
+use num_bigint::BigUint;
+
+static INPUT: &str = include_str!("input/day11.txt");
 
 type Worry = usize;
-struct Monkey {
+
+/// Number type worry levels are tracked with.
+///
+/// Arithmetic returns `None` when the result doesn't fit, or would go below zero.
+trait WorryLevel: Clone + std::fmt::Debug + std::fmt::Display {
+    fn from_worry(w: Worry) -> Self;
+    fn is_zero(&self) -> bool;
+    fn try_add(&self, rhs: &Self) -> Option<Self>;
+    fn try_sub(&self, rhs: &Self) -> Option<Self>;
+    fn try_mul(&self, rhs: &Self) -> Option<Self>;
+    /// Panics if `rhs` is zero
+    fn div(&self, rhs: &Self) -> Self;
+    /// Panics if `rhs` is zero
+    fn rem(&self, rhs: &Self) -> Self;
+    /// Remainder after dividing by a divisor that fits into [`Worry`]
+    fn rem_worry(&self, d: Worry) -> Worry;
+}
+
+impl WorryLevel for Worry {
+    fn from_worry(w: Worry) -> Self { w }
+    fn is_zero(&self) -> bool { *self == 0 }
+    fn try_add(&self, rhs: &Self) -> Option<Self> { self.checked_add(*rhs) }
+    fn try_sub(&self, rhs: &Self) -> Option<Self> { self.checked_sub(*rhs) }
+    fn try_mul(&self, rhs: &Self) -> Option<Self> { self.checked_mul(*rhs) }
+    fn div(&self, rhs: &Self) -> Self { self / rhs }
+    fn rem(&self, rhs: &Self) -> Self { self % rhs }
+    fn rem_worry(&self, d: Worry) -> Worry { self % d }
+}
+
+/// Arbitrary precision, only subtraction below zero can fail
+impl WorryLevel for BigUint {
+    fn from_worry(w: Worry) -> Self { BigUint::from(w) }
+    fn is_zero(&self) -> bool { self.bits() == 0 }
+    fn try_add(&self, rhs: &Self) -> Option<Self> { Some(self + rhs) }
+    fn try_sub(&self, rhs: &Self) -> Option<Self> { (self >= rhs).then(|| self - rhs) }
+    fn try_mul(&self, rhs: &Self) -> Option<Self> { Some(self * rhs) }
+    fn div(&self, rhs: &Self) -> Self { self / rhs }
+    fn rem(&self, rhs: &Self) -> Self { self % rhs }
+    fn rem_worry(&self, d: Worry) -> Worry {
+        Worry::try_from(self % d).expect("remainder is smaller than divisor")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+impl BinOp {
+    /// Higher binds tighter
+    fn precedence(&self) -> u8 {
+        match self {
+            BinOp::Add | BinOp::Sub => 1,
+            BinOp::Mul | BinOp::Div | BinOp::Rem => 2,
+        }
+    }
+
+    fn symbol(&self) -> char {
+        match self {
+            BinOp::Add => '+',
+            BinOp::Sub => '-',
+            BinOp::Mul => '*',
+            BinOp::Div => '/',
+            BinOp::Rem => '%',
+        }
+    }
+}
+
+/// Inspection operation, right hand side of `new = ...`
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Expr {
+    /// Worry level before inspection
+    Old,
+    Const(Worry),
+    Binary(Box<Expr>, BinOp, Box<Expr>),
+}
+
+#[derive(Debug)]
+enum EvalError {
+    /// Result of `expr` didn't fit into the worry type or went below zero
+    Overflow { expr: String, old: String },
+    DivisionByZero { expr: String, old: String },
+}
+
+impl std::fmt::Display for EvalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EvalError::Overflow { expr, old } => write!(f, "overflow in '{expr}' with old = {old}"),
+            EvalError::DivisionByZero { expr, old } => write!(f, "division by zero in '{expr}' with old = {old}"),
+        }
+    }
+}
+
+impl Expr {
+    /// Takes old worry level and returns a new one
+    fn eval<W: WorryLevel>(&self, old: &W) -> Result<W, EvalError> {
+        match self {
+            Expr::Old => Ok(old.clone()),
+            Expr::Const(c) => Ok(W::from_worry(*c)),
+            Expr::Binary(lhs, op, rhs) => {
+                let (l, r) = (lhs.eval(old)?, rhs.eval(old)?);
+                let result = match op {
+                    BinOp::Add => l.try_add(&r),
+                    BinOp::Sub => l.try_sub(&r),
+                    BinOp::Mul => l.try_mul(&r),
+                    BinOp::Div | BinOp::Rem if r.is_zero() => {
+                        return Err(EvalError::DivisionByZero { expr: self.to_string(), old: old.to_string() });
+                    },
+                    BinOp::Div => Some(l.div(&r)),
+                    BinOp::Rem => Some(l.rem(&r)),
+                };
+
+                result.ok_or_else(|| EvalError::Overflow { expr: self.to_string(), old: old.to_string() })
+            },
+        }
+    }
+
+    /// Returns `true` if the expression only adds and multiplies.
+    /// Only then does reducing `old` modulo some number give the same remainder for the result.
+    fn is_ring_op(&self) -> bool {
+        match self {
+            Expr::Old | Expr::Const(_) => true,
+            Expr::Binary(lhs, BinOp::Add | BinOp::Mul, rhs) => lhs.is_ring_op() && rhs.is_ring_op(),
+            Expr::Binary(..) => false,
+        }
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            Expr::Binary(_, op, _) => op.precedence(),
+            _ => u8::MAX,
+        }
+    }
+}
+
+impl std::fmt::Display for Expr {
+    /// Writes the expression with only necessary parentheses
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expr::Old => write!(f, "old"),
+            Expr::Const(c) => write!(f, "{c}"),
+            Expr::Binary(lhs, op, rhs) => {
+                // Operators are left associative, so equal precedence on the right needs parentheses
+                if lhs.precedence() < op.precedence() {
+                    write!(f, "({lhs})")?;
+                } else {
+                    write!(f, "{lhs}")?;
+                }
+
+                write!(f, " {} ", op.symbol())?;
+
+                if rhs.precedence() <= op.precedence() {
+                    write!(f, "({rhs})")
+                } else {
+                    write!(f, "{rhs}")
+                }
+            },
+        }
+    }
+}
+
+struct Monkey<W = Worry> {
     /// Item worry levels
-    items: VecDeque<Worry>,
-    /// Inspection function pointer, takes old worry level and returns a new one
-    op: Box<dyn Fn(Worry) -> Worry>,
+    items: VecDeque<W>,
+    /// Inspection operation
+    op: Expr,
     /// Tuple containing divisor, and target monkeys for true and false paths
     test: (Worry, usize, usize),
     /// How many times has this monkey inspected an item
     inspections: usize,
 }
 
+#[derive(Debug)]
+enum ParseError {
+    /// Block ended before line starting with `expected`
+    MissingLine { expected: &'static str },
+    /// Line didn't start with `expected`
+    Malformed { expected: &'static str, found: String },
+    InvalidNumber(String),
+    /// Operation couldn't be parsed, with the reason
+    InvalidExpression { expr: String, reason: &'static str },
+    /// Monkey throws to a monkey that doesn't exist
+    UnknownTarget(usize),
+    /// Monkey throws to itself, it would keep inspecting the same item forever
+    ThrowsToItself,
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::MissingLine { expected } => write!(f, "missing line '{expected}'"),
+            ParseError::Malformed { expected, found } => write!(f, "expected '{expected}', found '{found}'"),
+            ParseError::InvalidNumber(s) => write!(f, "invalid number '{s}'"),
+            ParseError::InvalidExpression { expr, reason } => write!(f, "invalid operation '{expr}': {reason}"),
+            ParseError::UnknownTarget(t) => write!(f, "throws to nonexistent monkey {t}"),
+            ParseError::ThrowsToItself => write!(f, "throws to itself"),
+        }
+    }
+}
+
+fn number<T: FromStr>(s: &str) -> Result<T, ParseError> {
+    s.trim().parse().map_err(|_| ParseError::InvalidNumber(s.trim().into()))
+}
+
+/// Recursive descent parser for [`Expr`]
+///
+/// ```text
+/// expr   = term (("+" | "-") term)*
+/// term   = factor (("*" | "/" | "%") factor)*
+/// factor = "old" | number | "(" expr ")"
+/// ```
+struct ExprParser<'a> {
+    s: &'a str,
+    /// Byte offset of the next unparsed character
+    pos: usize,
+}
+
+impl ExprParser<'_> {
+    fn error(&self, reason: &'static str) -> ParseError {
+        ParseError::InvalidExpression { expr: self.s.trim().into(), reason }
+    }
+
+    /// Next non-whitespace character, without consuming it
+    fn peek(&mut self) -> Option<char> {
+        let rest = &self.s[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+        self.s[self.pos..].chars().next()
+    }
+
+    /// Consumes the next character if it's one of `ops`
+    fn operator(&mut self, ops: &[BinOp]) -> Option<BinOp> {
+        let c = self.peek()?;
+        let op = ops.iter().copied().find(|op| op.symbol() == c)?;
+        self.pos += c.len_utf8();
+        Some(op)
+    }
+
+    fn expr(&mut self) -> Result<Expr, ParseError> {
+        let mut lhs = self.term()?;
+        while let Some(op) = self.operator(&[BinOp::Add, BinOp::Sub]) {
+            lhs = Expr::Binary(Box::new(lhs), op, Box::new(self.term()?));
+        }
+        Ok(lhs)
+    }
+
+    fn term(&mut self) -> Result<Expr, ParseError> {
+        let mut lhs = self.factor()?;
+        while let Some(op) = self.operator(&[BinOp::Mul, BinOp::Div, BinOp::Rem]) {
+            lhs = Expr::Binary(Box::new(lhs), op, Box::new(self.factor()?));
+        }
+        Ok(lhs)
+    }
+
+    fn factor(&mut self) -> Result<Expr, ParseError> {
+        match self.peek() {
+            Some('(') => {
+                self.pos += 1;
+                let inner = self.expr()?;
+                match self.peek() {
+                    Some(')') => {
+                        self.pos += 1;
+                        Ok(inner)
+                    },
+                    _ => Err(self.error("missing ')'")),
+                }
+            },
+            Some(c) if c.is_ascii_digit() => {
+                let rest = &self.s[self.pos..];
+                let len = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+                self.pos += len;
+                number(&rest[..len]).map(Expr::Const)
+            },
+            Some(_) if self.s[self.pos..].starts_with("old") => {
+                self.pos += "old".len();
+                Ok(Expr::Old)
+            },
+            Some(_) => Err(self.error("expected 'old', a number or '('")),
+            None => Err(self.error("unexpected end")),
+        }
+    }
+}
+
+impl FromStr for Expr {
+    type Err = ParseError;
+
+    /// Parses right hand side of the operation line, eg. `old * 19`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = ExprParser { s, pos: 0 };
+        let expr = parser.expr()?;
+
+        match parser.peek() {
+            None => Ok(expr),
+            Some(_) => Err(parser.error("unexpected trailing input")),
+        }
+    }
+}
+
+impl FromStr for Monkey {
+    type Err = ParseError;
+
+    /// Parses a single block of notes:
+    /// ```text
+    /// Monkey 0:
+    ///   Starting items: 79, 98
+    ///   Operation: new = old * 19
+    ///   Test: divisible by 23
+    ///     If true: throw to monkey 2
+    ///     If false: throw to monkey 3
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines().map(str::trim);
+
+        // Returns rest of the next line after `expected` prefix
+        let mut field = |expected: &'static str| {
+            let line = lines.next().ok_or(ParseError::MissingLine { expected })?;
+            line.strip_prefix(expected)
+                .ok_or_else(|| ParseError::Malformed { expected, found: line.into() })
+        };
+
+        field("Monkey ")?; // Monkeys are numbered by their position
+        let items = field("Starting items:")?;
+        let op = field("Operation: new =")?;
+        let divisor = field("Test: divisible by ")?;
+        let if_true = field("If true: throw to monkey ")?;
+        let if_false = field("If false: throw to monkey ")?;
+
+        let items = items.split(',')
+            .filter(|item| !item.trim().is_empty())
+            .map(number)
+            .collect::<Result<_, _>>()?;
+
+        let divisor = match number(divisor)? {
+            0 => return Err(ParseError::InvalidNumber(divisor.trim().into())), // Can't test divisibility by zero
+            d => d,
+        };
+
+        Ok(Monkey {
+            items,
+            op: op.parse()?,
+            test: (divisor, number(if_true)?, number(if_false)?),
+            inspections: 0,
+        })
+    }
+}
+
+/// Parses every block of notes, failing with index of the first bad monkey
+fn parse(s: &str) -> Result<Vec<Monkey>, (usize, ParseError)> {
+    let monkeys: Vec<Monkey> = s.split("\n\n")
+        .filter(|block| !block.trim().is_empty())
+        .enumerate()
+        .map(|(i, block)| block.parse().map_err(|e| (i, e)))
+        .collect::<Result<_, _>>()?;
+
+    for (i, monkey) in monkeys.iter().enumerate() {
+        let (_, if_true, if_false) = monkey.test;
+        if let Some(&target) = [if_true, if_false].iter().find(|&&t| t >= monkeys.len()) {
+            return Err((i, ParseError::UnknownTarget(target)));
+        }
+        if if_true == i || if_false == i {
+            return Err((i, ParseError::ThrowsToItself));
+        }
+    }
+
+    Ok(monkeys)
+}
+
 impl Monkey {
-    fn new(
-        items: Vec<Worry>,
-        op: impl Fn(Worry) -> Worry + 'static,
-        test: (Worry, usize, usize)
-    ) -> Self {
-        Self {
-            items: items.into(),
-            op: Box::new(op),
-            test,
-            inspections: 0
-        }
-    }
-}
-
-pub fn silver_and_gold<const N: usize, const S: bool>() {
-    let _example = [
-        Monkey::new(vec![79, 98],         |old| old * 19,  (23, 2, 3)),
-        Monkey::new(vec![54, 65, 75, 74], |old| old + 6,   (19, 2, 0)),
-        Monkey::new(vec![79, 60, 97],     |old| old * old, (13, 1, 3)),
-        Monkey::new(vec![74],             |old| old + 3,   (17, 0, 1)),
-    ];
-
-    let _real = [
-        Monkey::new(vec![56, 52, 58, 96, 70, 75, 72],     |old| old * 17,  (11, 2, 3)),
-        Monkey::new(vec![75, 58, 86, 80, 55, 81],         |old| old + 7,   (3,  6, 5)),
-        Monkey::new(vec![73, 68, 73, 90],                 |old| old * old, (5,  1, 7)),
-        Monkey::new(vec![72, 89, 55, 51, 59],             |old| old + 1,   (7,  2, 7)),
-        Monkey::new(vec![76, 76, 91],                     |old| old * 3,   (19, 0, 3)),
-        Monkey::new(vec![88],                             |old| old + 4,   (2,  6, 4)),
-        Monkey::new(vec![64, 63, 56, 50, 77, 55, 55, 86], |old| old + 8,   (13, 4, 0)),
-        Monkey::new(vec![79, 58],                         |old| old + 6,   (17, 1, 5)),
-    ];
-
-    let mut input = _real;
-    // Product of all test divisors
-    // for more optimal version, calculate least-common multiple
-    let prod = input.iter().fold(1, |acc, m| acc * m.test.0);
-
-    for _round in 0..N  {
-        for monkey_i in 0..input.len() {
-            while let Some(item) = input[monkey_i].items.pop_front() {
-                input[monkey_i].inspections += 1;
+    /// Same monkey tracking worry levels with another number type
+    fn convert<W: WorryLevel>(self) -> Monkey<W> {
+        Monkey {
+            items: self.items.into_iter().map(W::from_worry).collect(),
+            op: self.op,
+            test: self.test,
+            inspections: self.inspections,
+        }
+    }
+}
+
+/// What happens to worry level after a monkey has inspected an item
+#[derive(Debug, Clone, Copy)]
+pub enum Relief {
+    /// Worry level is divided by given number, rounding down
+    Divide(Worry),
+    /// Worry level is wrapped to least common multiple of all test divisors.
+    /// Keeps numbers small without changing any divisibility test,
+    /// as long as operations only add and multiply.
+    Modulo,
+    /// Worry level only ever grows
+    None,
+}
+
+fn gcd(a: Worry, b: Worry) -> Worry {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+/// Modulus for [`Relief::Modulo`], fails if some operation would give wrong results with it
+fn modulus(monkeys: &[Monkey<impl WorryLevel>]) -> Result<Worry, SimulationError> {
+    if let Some((i, m)) = monkeys.iter().enumerate().find(|(_, m)| !m.op.is_ring_op()) {
+        return Err(SimulationError::NotModular(i, m.op.clone()));
+    }
+    lcm(monkeys)
+}
+
+/// Least common multiple of all test divisors
+fn lcm(monkeys: &[Monkey<impl WorryLevel>]) -> Result<Worry, SimulationError> {
+    monkeys.iter().try_fold(1, |acc: Worry, m| {
+        let d = m.test.0;
+        (acc / gcd(acc, d)).checked_mul(d).ok_or(SimulationError::LcmOverflow)
+    })
+}
+
+#[derive(Debug)]
+enum SimulationError {
+    /// Operation of the monkey at given index failed
+    Eval(usize, EvalError),
+    /// Least common multiple of the test divisors doesn't fit into [`Worry`]
+    LcmOverflow,
+    /// [`Relief::Divide`] by zero
+    ReliefByZero,
+    /// Operation of the monkey at given index can't be used with [`Relief::Modulo`]
+    NotModular(usize, Expr),
+}
+
+impl std::fmt::Display for SimulationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SimulationError::Eval(i, e) => write!(f, "monkey {i}: {e}"),
+            SimulationError::LcmOverflow => write!(f, "least common multiple of test divisors overflows"),
+            SimulationError::ReliefByZero => write!(f, "cannot divide worry by zero"),
+            SimulationError::NotModular(i, op) => write!(f, "monkey {i}: '{op}' can't be reduced modulo LCM, only + and * can"),
+        }
+    }
+}
+
+/// Per-round inspection counts and throws between monkeys
+#[derive(Debug, Default)]
+struct Recorder {
+    /// Inspections per monkey, one entry per round
+    inspections: Vec<Vec<usize>>,
+    /// Throw counts indexed by `from * monkey_count + to`, one entry per round
+    throws: Vec<Vec<usize>>,
+}
+
+impl Recorder {
+    fn monkey_count(&self) -> usize {
+        self.inspections.first().map_or(0, Vec::len)
+    }
+
+    fn start_round(&mut self, monkeys: usize) {
+        self.inspections.push(vec![0; monkeys]);
+        self.throws.push(vec![0; monkeys * monkeys]);
+    }
+
+    fn inspect(&mut self, monkey: usize) {
+        self.inspections.last_mut().unwrap()[monkey] += 1;
+    }
+
+    fn throw(&mut self, from: usize, to: usize) {
+        let n = self.monkey_count();
+        self.throws.last_mut().unwrap()[from * n + to] += 1;
+    }
+
+    /// Throw counts summed over every round, indexed like a single round
+    fn total_throws(&self) -> Vec<usize> {
+        let n = self.monkey_count();
+        self.throws.iter().fold(vec![0; n * n], |mut acc, round| {
+            acc.iter_mut().zip(round).for_each(|(a, t)| *a += t);
+            acc
+        })
+    }
+
+    /// `round,monkey,inspections` with rounds numbered from 1
+    fn inspections_csv(&self) -> String {
+        let mut out = String::from("round,monkey,inspections\n");
+        for (round, counts) in self.inspections.iter().enumerate() {
+            for (monkey, count) in counts.iter().enumerate() {
+                out.push_str(&format!("{},{monkey},{count}\n", round + 1));
+            }
+        }
+        out
+    }
+
+    /// `round,from,to,count`, pairs that didn't throw anything that round are left out
+    fn throws_csv(&self) -> String {
+        let n = self.monkey_count();
+        let mut out = String::from("round,from,to,count\n");
+        for (round, throws) in self.throws.iter().enumerate() {
+            for (i, &count) in throws.iter().enumerate().filter(|(_, &c)| c > 0) {
+                out.push_str(&format!("{},{},{},{count}\n", round + 1, i / n, i % n));
+            }
+        }
+        out
+    }
+
+    /// Graphviz digraph of monkeys labelled with total inspections,
+    /// edges labelled with total throws and drawn thicker the more items went through them
+    fn to_dot(&self) -> String {
+        let n = self.monkey_count();
+        let throws = self.total_throws();
+        let max = throws.iter().copied().max().unwrap_or(0).max(1);
+
+        let mut out = String::from("digraph keep_away {\n");
+        for monkey in 0..n {
+            let inspections: usize = self.inspections.iter().map(|round| round[monkey]).sum();
+            out.push_str(&format!("    m{monkey} [label=\"Monkey {monkey}\\n{inspections} inspections\"];\n"));
+        }
+        for (i, &count) in throws.iter().enumerate().filter(|(_, &c)| c > 0) {
+            let width = 1.0 + 4.0 * count as f64 / max as f64;
+            out.push_str(&format!("    m{} -> m{} [label=\"{count}\", weight={count}, penwidth={width:.2}];\n", i / n, i % n));
+        }
+        out.push_str("}\n");
+        out
+    }
+}
+
+/// Plays `rounds` rounds of keep away.
+/// Every round is written to `recorder` if there is one.
+fn simulate<W: WorryLevel>(monkeys: &mut [Monkey<W>], rounds: usize, relief: Relief, mut recorder: Option<&mut Recorder>) -> Result<(), SimulationError> {
+    let relief_by = match relief {
+        Relief::Divide(0) => return Err(SimulationError::ReliefByZero),
+        Relief::Divide(k) => Some(W::from_worry(k)),
+        Relief::Modulo => Some(W::from_worry(modulus(monkeys)?)),
+        Relief::None => None,
+    };
+
+    for _round in 0..rounds {
+        if let Some(r) = recorder.as_deref_mut() {
+            r.start_round(monkeys.len());
+        }
+
+        for monkey_i in 0..monkeys.len() {
+            while let Some(item) = monkeys[monkey_i].items.pop_front() {
+                monkeys[monkey_i].inspections += 1;
+                if let Some(r) = recorder.as_deref_mut() {
+                    r.inspect(monkey_i);
+                }
 
                 // Calculate new worry level
-                let item = if S { // Solving silver
-                    (input[monkey_i].op)(item) / 3
-                } else { // Solving gold
+                let new = monkeys[monkey_i].op.eval(&item).map_err(|e| SimulationError::Eval(monkey_i, e))?;
+                let item = match (relief, &relief_by) {
+                    (Relief::Divide(_), Some(k)) => new.div(k),
                     // Modulo operation here effectively wraps worry level into between
-                    // [0, w_0 * w_1 * ... * w_m), where `m` is number of monkeys
+                    // [0, lcm(w_0, w_1, ..., w_m)), where `m` is number of monkeys
                     // Resulting number can then be used in divisibility check below.
-                    (input[monkey_i].op)(item) % prod
+                    (Relief::Modulo, Some(lcm)) => new.rem(lcm),
+                    _ => new,
                 };
 
                 // Which monkey should item be thrown at
-                let target_i = if item % input[monkey_i].test.0 == 0 {
-                    input[monkey_i].test.1
+                let target_i = if item.rem_worry(monkeys[monkey_i].test.0) == 0 {
+                    monkeys[monkey_i].test.1
                 } else {
-                    input[monkey_i].test.2
+                    monkeys[monkey_i].test.2
                 };
 
                 //println!("Throwing {item} from {monkey_i} to {target_i}");
-                input[target_i].items.push_back(item);
+                if let Some(r) = recorder.as_deref_mut() {
+                    r.throw(monkey_i, target_i);
+                }
+                monkeys[target_i].items.push_back(item);
             }
         }
     }
 
+    Ok(())
+}
+
+fn run<W: WorryLevel>(rounds: usize, relief: Relief) {
+    let mut input: Vec<Monkey<W>> = match parse(INPUT) {
+        Ok(monkeys) => monkeys.into_iter().map(Monkey::convert).collect(),
+        Err((i, e)) => {
+            eprintln!("monkey {i}: {e}");
+            return;
+        },
+    };
+
+    if let Err(e) = simulate(&mut input, rounds, relief, None) {
+        eprintln!("{e}");
+        return;
+    }
+
     for monke in &input {
-        println!("Inventory: {:?} | count: {}", monke.items, monke.inspections);
+        println!("Inventory: {:?} | count: {} | new = {}", monke.items, monke.inspections, monke.op);
+    }
+
+    match monkey_business(input.iter().map(|m| m.inspections as u64)) {
+        Some(monkey_business) => println!("Monkey business: {monkey_business}"),
+        None => println!("Monkey business needs at least two monkeys"),
+    }
+}
+
+/// Product of the two highest inspection counts, `None` with less than two monkeys.
+/// Could exceed u64 with enough rounds.
+fn monkey_business(inspections: impl IntoIterator<Item = u64>) -> Option<u128> {
+    let mut counts: Vec<u64> = inspections.into_iter().collect();
+    counts.sort_unstable_by_key(|&c| std::cmp::Reverse(c));
+    match counts[..] {
+        [first, second, ..] => Some(first as u128 * second as u128),
+        _ => None,
+    }
+}
+
+/// Plays `rounds` rounds tracking worry levels with native integers, overflow is reported
+pub fn silver_and_gold(rounds: usize, relief: Relief) {
+    run::<Worry>(rounds, relief);
+}
+
+/// Same as [`silver_and_gold`] but with arbitrary precision worry levels.
+/// Exact even without relief, but numbers grow fast so keep round count small.
+pub fn silver_and_gold_exact(rounds: usize, relief: Relief) {
+    run::<BigUint>(rounds, relief);
+}
+
+/// Plays `rounds` rounds and writes per-round inspections to `{prefix}inspections.csv`,
+/// per-round throws to `{prefix}throws.csv` and total item flow as a Graphviz graph to `{prefix}throws.dot`.
+/// Fails with [`std::io::ErrorKind::InvalidData`] if the notes can't be parsed or the simulation fails.
+pub fn record(rounds: usize, relief: Relief, prefix: &str) -> std::io::Result<()> {
+    let invalid = |msg: String| std::io::Error::new(std::io::ErrorKind::InvalidData, msg);
+
+    let mut monkeys = parse(INPUT).map_err(|(i, e)| invalid(format!("monkey {i}: {e}")))?;
+    let mut recorder = Recorder::default();
+    simulate(&mut monkeys, rounds, relief, Some(&mut recorder)).map_err(|e| invalid(e.to_string()))?;
+
+    std::fs::write(format!("{prefix}inspections.csv"), recorder.inspections_csv())?;
+    std::fs::write(format!("{prefix}throws.csv"), recorder.throws_csv())?;
+    std::fs::write(format!("{prefix}throws.dot"), recorder.to_dot())?;
+    Ok(())
+}
+
+/// Follows a single item through one round with [`Relief::Modulo`], starting at `monkey` before its turn.
+///
+/// Items never affect each other, so each one can be followed on its own.
+/// Adds inspections to `counts` and returns where the item is at the start of the next round.
+fn item_round(monkeys: &[Monkey], lcm: Worry, (mut monkey, mut worry): (usize, Worry), counts: &mut [u64]) -> Result<(usize, Worry), SimulationError> {
+    loop {
+        counts[monkey] += 1;
+        worry = monkeys[monkey].op.eval(&worry).map_err(|e| SimulationError::Eval(monkey, e))? % lcm;
+
+        let (divisor, if_true, if_false) = monkeys[monkey].test;
+        let target = if worry % divisor == 0 { if_true } else { if_false };
+
+        // Monkeys later in the order get their turn this same round
+        if target <= monkey {
+            return Ok((target, worry));
+        }
+        monkey = target;
+    }
+}
+
+/// Brent's cycle detection, returns `(prefix, period)`: states repeat every `period` steps
+/// after the first `prefix` steps. Only keeps a couple of states around, however long the cycle is.
+fn find_cycle<S: Copy + PartialEq, E>(start: S, mut f: impl FnMut(S) -> Result<S, E>) -> Result<(u64, u64), E> {
+    // Find period by moving tortoise to hare at every power of two
+    let (mut power, mut period) = (1, 1);
+    let mut tortoise = start;
+    let mut hare = f(start)?;
+    while tortoise != hare {
+        if power == period {
+            tortoise = hare;
+            power *= 2;
+            period = 0;
+        }
+        hare = f(hare)?;
+        period += 1;
+    }
+
+    // Hare `period` steps ahead, they meet at the start of the cycle
+    let (mut tortoise, mut hare) = (start, start);
+    for _ in 0..period {
+        hare = f(hare)?;
+    }
+    let mut prefix = 0;
+    while tortoise != hare {
+        tortoise = f(tortoise)?;
+        hare = f(hare)?;
+        prefix += 1;
+    }
+
+    Ok((prefix, period))
+}
+
+/// Inspections per monkey after `rounds` rounds with [`Relief::Modulo`].
+///
+/// Every item goes through (monkey, worry mod LCM) states which must eventually repeat,
+/// so whole cycles are counted once and multiplied. Takes time proportional to the cycle lengths,
+/// not to `rounds`. Like [`Relief::Modulo`], only works when operations add and multiply.
+fn long_run_inspections(monkeys: &[Monkey], rounds: u64) -> Result<Vec<u64>, SimulationError> {
+    let lcm = modulus(monkeys)?;
+    let mut counts = vec![0; monkeys.len()];
+    let mut scratch = vec![0; monkeys.len()];
+
+    for (monkey_i, monkey) in monkeys.iter().enumerate() {
+        for &item in &monkey.items {
+            let start = (monkey_i, item);
+            let (prefix, period) = find_cycle(start, |s| item_round(monkeys, lcm, s, &mut scratch))?;
+
+            // Rounds before the cycle
+            let mut state = start;
+            for _ in 0..rounds.min(prefix) {
+                state = item_round(monkeys, lcm, state, &mut counts)?;
+            }
+            if rounds <= prefix {
+                continue;
+            }
+
+            // Whole cycles
+            let (cycles, rest) = ((rounds - prefix) / period, (rounds - prefix) % period);
+            let mut cycle = vec![0; monkeys.len()];
+            for _ in 0..period {
+                state = item_round(monkeys, lcm, state, &mut cycle)?;
+            }
+            for (count, c) in counts.iter_mut().zip(cycle) {
+                *count += c * cycles;
+            }
+
+            // Partial cycle at the end, state is back at the start of the cycle
+            for _ in 0..rest {
+                state = item_round(monkeys, lcm, state, &mut counts)?;
+            }
+        }
+    }
+
+    Ok(counts)
+}
+
+/// Plays `rounds` rounds with [`Relief::Modulo`] using cycle detection, works for rounds like 10^12
+pub fn long_run(rounds: u64) {
+    let monkeys = match parse(INPUT) {
+        Ok(monkeys) => monkeys,
+        Err((i, e)) => {
+            eprintln!("monkey {i}: {e}");
+            return;
+        },
+    };
+
+    let counts = match long_run_inspections(&monkeys, rounds) {
+        Ok(counts) => counts,
+        Err(e) => {
+            eprintln!("{e}");
+            return;
+        },
+    };
+
+    for (i, count) in counts.iter().enumerate() {
+        println!("Monkey {i} | count: {count}");
+    }
+
+    match monkey_business(counts) {
+        Some(monkey_business) => println!("Monkey business after {rounds} rounds: {monkey_business}"),
+        None => println!("Monkey business needs at least two monkeys"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+Monkey 0:
+  Starting items: 79, 98
+  Operation: new = old * 19
+  Test: divisible by 23
+    If true: throw to monkey 2
+    If false: throw to monkey 3
+
+Monkey 1:
+  Starting items: 54, 65, 75, 74
+  Operation: new = old + 6
+  Test: divisible by 19
+    If true: throw to monkey 2
+    If false: throw to monkey 0
+
+Monkey 2:
+  Starting items: 79, 60, 97
+  Operation: new = old * old
+  Test: divisible by 13
+    If true: throw to monkey 1
+    If false: throw to monkey 3
+
+Monkey 3:
+  Starting items: 74
+  Operation: new = old + 3
+  Test: divisible by 17
+    If true: throw to monkey 0
+    If false: throw to monkey 1
+";
+
+    #[test]
+    fn relief_by_zero_is_an_error() {
+        let mut monkeys = parse(EXAMPLE).unwrap();
+        let result = simulate(&mut monkeys, 1, Relief::Divide(0), None);
+        assert!(matches!(result, Err(SimulationError::ReliefByZero)));
+    }
+
+    #[test]
+    fn lcm_overflow_is_an_error() {
+        // Two primes above 2^32, their product doesn't fit into 64 bits
+        let notes = EXAMPLE.replace("divisible by 23", "divisible by 4294967311")
+            .replace("divisible by 19", "divisible by 4294967357");
+        let mut monkeys = parse(&notes).unwrap();
+
+        let result = simulate(&mut monkeys, 1, Relief::Modulo, None);
+        assert!(matches!(result, Err(SimulationError::LcmOverflow)));
+        assert!(matches!(long_run_inspections(&monkeys, 1), Err(SimulationError::LcmOverflow)));
+    }
+
+    #[test]
+    fn long_run_matches_simulation() {
+        let monkeys = parse(EXAMPLE).unwrap();
+        let mut simulated = parse(EXAMPLE).unwrap();
+
+        // Example items enter cycles of at most 448 rounds within 175 rounds,
+        // so this covers prefixes, whole cycles and partial ones
+        for round in 1..=1000 {
+            simulate(&mut simulated, 1, Relief::Modulo, None).unwrap();
+
+            let expected: Vec<u64> = simulated.iter().map(|m| m.inspections as u64).collect();
+            assert_eq!(long_run_inspections(&monkeys, round).unwrap(), expected, "inspections differ after {round} rounds");
+        }
+    }
+
+    #[test]
+    fn long_run_gold() {
+        let monkeys = parse(EXAMPLE).unwrap();
+        assert_eq!(long_run_inspections(&monkeys, 10_000).unwrap(), [52166, 47830, 1938, 52013]);
+    }
+
+    #[test]
+    fn throwing_to_itself_is_rejected() {
+        let notes = EXAMPLE.replace("If false: throw to monkey 3\n\nMonkey 1", "If false: throw to monkey 0\n\nMonkey 1");
+        assert!(matches!(parse(&notes), Err((0, ParseError::ThrowsToItself))));
+    }
+
+    #[test]
+    fn monkey_business_needs_two_monkeys() {
+        assert_eq!(monkey_business([]), None);
+        assert_eq!(monkey_business([5]), None);
+        assert_eq!(monkey_business([101, 95, 7, 105]), Some(10605));
     }
 
-    // Monkey business
-    input.sort_unstable_by(|a, b| b.inspections.cmp(&a.inspections));
-    let monkey_business = input[0].inspections * input[1].inspections;
+    #[test]
+    fn ring_ops() {
+        let ring = |op: &str| op.parse::<Expr>().unwrap().is_ring_op();
+        assert!(ring("old"));
+        assert!(ring("7"));
+        assert!(ring("old * old + 3"));
+        assert!(ring("(old + 1) * (old + 2)"));
+        assert!(!ring("old - 3"));
+        assert!(!ring("old * 8 / 3"));
+        assert!(!ring("old % 7 + 1"));
+        assert!(!ring("(old - 1) * 2"));
+    }
+
+    #[test]
+    fn modulo_rejects_non_ring_ops() {
+        let notes = EXAMPLE.replace("new = old + 6", "new = old * 8 / 3");
+        let mut monkeys = parse(&notes).unwrap();
 
-    println!("Monkey business: {monkey_business}");
+        let result = simulate(&mut monkeys, 1, Relief::Modulo, None);
+        assert!(matches!(result, Err(SimulationError::NotModular(1, _))));
+
+        // Other reliefs stay exact
+        assert!(simulate(&mut monkeys, 1, Relief::Divide(3), None).is_ok());
+    }
+
+    #[test]
+    fn long_run_rejects_non_ring_ops() {
+        for op in ["old * 8 / 3", "old - 1", "old % 5"] {
+            let notes = EXAMPLE.replace("new = old + 6", &format!("new = {op}"));
+            let monkeys = parse(&notes).unwrap();
+            assert!(matches!(long_run_inspections(&monkeys, 10), Err(SimulationError::NotModular(1, _))), "accepted '{op}'");
+        }
+    }
 }
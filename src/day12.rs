@@ -1,198 +1,170 @@
-static INPUT: &'static str = include_str!("input/day12.txt");
+use std::str::FromStr;
 
-#[derive(Debug, Default)]
+use crate::grid;
+use crate::search;
+
+static INPUT: &str = include_str!("input/day12.txt");
+
+#[derive(Debug)]
 struct Grid {
-    vertices: Vec<u8>, // range a-z can be represented with u8
-    width: usize,
-    height: usize,
+    vertices: grid::Grid<u8>, // range a-z can be represented with u8
     start: usize, // Index of starting vertex S
     end: usize, // Index of ending vertex E
 }
 
-#[derive(Debug, Default)]
-/// Neighbours of some point
-/// Indices are connected to [`Grid`]
-struct Neighbours {
-    up: Option<usize>,
-    left: Option<usize>,
-    right: Option<usize>,
-    down: Option<usize>,
+#[derive(Debug)]
+enum ParseError {
+    /// Not a rectangle of `a`-`z`, `S` and `E`
+    Grid(grid::ParseError),
+    /// No `S` or `E` in the map
+    Missing(char),
+    /// `S` or `E` found at two coordinates
+    Duplicate { c: char, first: (usize, usize), second: (usize, usize) },
 }
 
-impl Neighbours {
-    /// Consumes self and returns neighbouring vertex indices in random order.
-    fn into_iter(self) -> impl Iterator<Item = usize> {
-        [self.up, self.left, self.down, self.right]
-            .into_iter()
-            .filter_map(|dir| dir)
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::Grid(e) => write!(f, "{e}"),
+            ParseError::Missing(c) => write!(f, "no '{c}' in heightmap"),
+            ParseError::Duplicate { c, first, second } => write!(f, "'{c}' at both {first:?} and {second:?}"),
+        }
     }
 }
 
-impl Grid {
-    fn new(s: &str, width: usize, height: usize) -> Self {
-        let mut grid = Grid::default();
-        grid.vertices.reserve_exact(width * height);
-        grid.width = width;
-        grid.height = height;
-
-        for (i, c) in s.as_bytes().into_iter().filter(|c| c.is_ascii_alphabetic()).enumerate() {
-            grid.vertices.push(match c {
-                b'S' => {
-                    grid.start = i;
-                    b'a'
-                },
-                b'E' => {
-                    grid.end = i;
-                    b'z'
-                },
-                _ => *c,
-            });
-        }
+impl FromStr for Grid {
+    type Err = ParseError;
+
+    /// Parses the heightmap, dimensions are taken from the text itself
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let chars = grid::Grid::parse(s, |c| (c.is_ascii_lowercase() || c == 'S' || c == 'E').then_some(c))
+            .map_err(ParseError::Grid)?;
+
+        // Index of the only `c` in the map
+        let find = |c: char| {
+            let mut found = chars.cells().iter().enumerate().filter(|&(_, &x)| x == c).map(|(i, _)| i);
+            match (found.next(), found.next()) {
+                (None, _) => Err(ParseError::Missing(c)),
+                (Some(first), Some(second)) => Err(ParseError::Duplicate { c, first: chars.coord(first), second: chars.coord(second) }),
+                (Some(i), None) => Ok(i),
+            }
+        };
+        let start = find('S')?;
+        let end = find('E')?;
 
-        grid
-    }
+        let vertices = chars.map(|&c| match c {
+            'S' => b'a',
+            'E' => b'z',
+            _ => c as u8,
+        });
 
-    fn get(&self, col: usize, row: usize) -> u8 {
-        self.vertices[row*self.width + col]
+        Ok(Grid { vertices, start, end })
     }
+}
 
-    fn get_i(&self, v: usize) -> u8 {
-        self.vertices[v]
+impl Grid {
+    fn get(&self, col: usize, row: usize) -> u8 {
+        self.vertices[(col, row)]
     }
 
-    /// Returns list of walkable vertex indices
-    fn neighbours(&self, col: usize, row: usize) -> Neighbours {
-        fn walkable_silver(current: u8, target: u8) -> bool { target <= current + 1 } 
-        fn walkable_gold(current: u8, target: u8) -> bool { current <= target + 1}
-
-        let walkable = walkable_gold;
-
-        let mut out = Neighbours::default();
-        let cur = self.get(col, row);
-
-        if let Some(up_i) = row.checked_sub(1) {
-            let t_i = self.width*up_i + col;
-            if walkable(cur, self.vertices[t_i]) {
-                out.up = Some(t_i);
-            }
+    /// Graph of steps allowed by `climb`
+    fn graph(&self, climb: Climb) -> search::GridGraph<'_, u8, impl Fn(&u8, &u8) -> Option<u64>> {
+        search::GridGraph {
+            grid: &self.vertices,
+            step: move |from: &u8, to: &u8| climb.walkable(*from, *to).then_some(1),
         }
+    }
 
-        if let Some(left_i) = col.checked_sub(1) {
-            let t_i = self.width*row + left_i;
-            if walkable(cur, self.vertices[t_i]) {
-                out.left = Some(t_i);
-            }
-        }
+    /// Shortest path from S to E following `climb`, both included
+    fn shortest_path(&self, climb: Climb) -> Option<Vec<(usize, usize)>> {
+        let (start, end) = (self.vertices.coord(self.start), self.vertices.coord(self.end));
+        search::bfs(&self.graph(climb), [start], |v| v == end).path(end)
+    }
 
-        if col + 1 <= self.width - 1 {
-            let t_i = self.width*row + col + 1;
-            if walkable(cur, self.vertices[t_i]) {
-                out.right = Some(t_i);
-            }
-        }
+    /// Shortest distance from any `a` to E following `climb`, and the path from that `a` to E.
+    ///
+    /// Searches backwards from E with the reversed rule, so the first `a` found is the closest one.
+    fn closest_a(&self, climb: Climb) -> Option<(u64, Vec<(usize, usize)>)> {
+        let end = self.vertices.coord(self.end);
+        let tree = search::bfs(&self.graph(climb.reversed()), [end], |(col, row)| self.get(col, row) == b'a');
+        let a = tree.goal?;
+
+        // Tree is rooted at E, so the path runs from E to `a`
+        let mut path = tree.path(a)?;
+        path.reverse();
+        Some((tree.distance(a)?, path))
+    }
+}
 
-        if row + 1 <= self.height - 1 {
-            let t_i = self.width*(row + 1) + col;
-            if walkable(cur, self.vertices[t_i]) {
-                out.down = Some(t_i);
-            }
-        }
+/// How much height may change in a single step
+#[derive(Debug, Clone, Copy)]
+pub struct Climb {
+    /// Highest step upwards
+    pub up: u8,
+    /// Highest step downwards
+    pub down: u8,
+}
 
-        out
-    }
+impl Climb {
+    /// Rule from the puzzle, at most one higher but any amount lower
+    pub const FORWARD: Climb = Climb { up: 1, down: u8::MAX };
 
-    /// Similar to [`Grid::neighbours`] but accepts coordinate as an index
-    fn neighbours_i(&self, v: usize) -> Neighbours {
-        let col = v / self.width;
-        let row = v % self.width;
-        self.neighbours(row, col)
+    /// Same rule for walking the path backwards
+    pub fn reversed(self) -> Climb {
+        Climb { up: self.down, down: self.up }
     }
 
-    /// Returns breadth-first tree starting from given root vertice index.
-    fn breadth_first_search(&self, root: usize) -> BreadthFirstSearchTree {
-        use std::collections::VecDeque;
-
-        // List of discovered vertices
-        let mut discovered = vec![false; self.vertices.len()];
-        // Vertices' parent nodes.
-        // Each vertex `v` (except root) has exactly one parent vertex `y`.
-        // Parent vertex can be found with `y = parents[v]`
-        // `y` is None if it's the root.
-        let mut parents = vec![None; self.vertices.len()];
-
-        // Queue of vertices to be visited
-        let mut queue: VecDeque<usize> = VecDeque::new();
-
-        queue.push_back(root);
-        discovered[root] = true;
-
-        while let Some(v) = queue.pop_front() {
-            // When solving Gold stop when first 'a' has been found.
-            // Write down found index on a piece of paper.
-            // if self.get_i(v) == b'a' {
-            //     println!("=== First `a` at index: {v} ===");
-            //     break;
-            // }
-
-            for y in self.neighbours_i(v).into_iter() {
-                if !discovered[y] {
-                    queue.push_back(y);
-                    discovered[y] = true;
-                    parents[y] = Some(v);
-                }
-            }
-        }
-
-        BreadthFirstSearchTree {
-            tree: parents,
-            root,
-            grid: &self,
+    fn walkable(self, current: u8, target: u8) -> bool {
+        if target >= current {
+            target - current <= self.up
+        } else {
+            current - target <= self.down
         }
     }
 }
 
-/// Represents result of Breadth First Search.
-/// Result is closely related to [`Grid`] and thus only lives as long the
-/// grid it was sourced from lives.
-struct BreadthFirstSearchTree<'a> {
-    tree: Vec<Option<usize>>,
-    root: usize,
-    grid: &'a Grid,
+fn print_silver(grid: &Grid, climb: Climb) {
+    match grid.shortest_path(climb) {
+        Some(path) => println!("Silver: {}", path.len() - 1), // Answer is the number of edges, not vertices
+        None => println!("Silver: E can't be reached from S"),
+    }
 }
 
-impl<'a> BreadthFirstSearchTree<'a> {
-    /// Returns shortest path to given index.
-    fn find_path(&self, end: Option<usize>) -> Vec<usize> {
-        let mut path: Vec<usize> = Vec::new();
-        let mut cursor = end;
-
-        while !(cursor.is_none() || cursor.unwrap() == self.root) {
-            path.push(cursor.unwrap());
-            cursor = self.tree[cursor.unwrap()];
-        }
+fn print_gold(grid: &Grid, climb: Climb) {
+    match grid.closest_a(climb) {
+        Some((distance, path)) => {
+            let (col, row) = path[0];
+            println!("Gold: {distance} starting from ({col}, {row})");
+        },
+        None => println!("Gold: no `a` can reach E"),
+    }
+}
 
-        path.push(self.root);
-        path
+/// Parses [`INPUT`], printing the error to stderr if there is one
+fn load() -> Option<Grid> {
+    match INPUT.parse() {
+        Ok(grid) => Some(grid),
+        Err(e) => {
+            eprintln!("{e}");
+            None
+        },
     }
 }
 
 pub fn silver() {
-    let grid = Grid::new(INPUT, 67, 41);
-
-    let tree = grid.breadth_first_search(grid.start);
-    let path = tree.find_path(Some(grid.end));
-    
-    println!("Silver: {}", path.len() - 1); // Answer is the number of edges, not vertices
+    let Some(grid) = load() else { return };
+    print_silver(&grid, Climb::FORWARD);
 }
 
 pub fn gold() {
-    let grid = Grid::new(INPUT, 67, 41);
-    // Found during tree construction in `Grid::breadth_first_search`
-    const MAGIC: usize = 1742;
-
-    // Tree is now formed starting from the end
-    let tree = grid.breadth_first_search(grid.end);
-    let path = tree.find_path(Some(MAGIC));
+    let Some(grid) = load() else { return };
+    print_gold(&grid, Climb::FORWARD);
+}
 
-    println!("Gold: {}", path.len() - 1);
+/// Both parts climbing at most `up` higher and `down` lower in a single step
+pub fn with_climb(up: u8, down: u8) {
+    let Some(grid) = load() else { return };
+    let climb = Climb { up, down };
+    print_silver(&grid, climb);
+    print_gold(&grid, climb);
 }
@@ -1,10 +1,22 @@
 use itertools::{Itertools, MinMaxResult};
 use std::collections::HashSet;
 
+use crate::search::{self, Graph};
+
 static INPUT: &'static str = include_str!("input/day18.txt");
 
 
 type Point = (i32, i32, i32);
+
+const NEARBY: [Point; 6] = [
+    (-1,  0,  0),
+    ( 1,  0,  0),
+    ( 0, -1,  0),
+    ( 0,  1,  0),
+    ( 0,  0, -1),
+    ( 0,  0,  1),
+];
+
 pub fn silver() {
     // Put all cubes into a set
     let grid: HashSet<Point> = HashSet::from_iter(INPUT.lines().map(|line| {
@@ -17,16 +29,7 @@ pub fn silver() {
     let mut total_free = 0;
     // For each cube, count nonexisting neighbours ie. free faces
     for cube in grid.iter() {
-        let nearby: [(i32, i32, i32); 6] = [
-            (-1,  0,  0),
-            ( 1,  0,  0),
-            ( 0, -1,  0),
-            ( 0,  1,  0),
-            ( 0,  0, -1),
-            ( 0,  0,  1),
-        ];
-
-        let free = nearby.iter().filter(|dir| {
+        let free = NEARBY.iter().filter(|dir| {
             let p = (cube.0 + dir.0, cube.1 + dir.1, cube.2 + dir.2);
 
             !grid.contains(&p)
@@ -37,6 +40,25 @@ pub fn silver() {
     println!("Silver: {total_free}\n");
 }
 
+/// Air cells within bounds, ie. everything that isn't a cube
+struct Air<'a> {
+    cubes: &'a HashSet<Point>,
+    bx: (i32, i32),
+    by: (i32, i32),
+    bz: (i32, i32),
+}
+
+impl Graph for Air<'_> {
+    type Node = Point;
+
+    fn neighbours(&self, v: Point) -> impl Iterator<Item = (Point, u64)> {
+        NEARBY.iter()
+            .map(move |d| (v.0 + d.0, v.1 + d.1, v.2 + d.2))
+            .filter(|p| in_bounds(p, &self.bx, &self.by, &self.bz) && !self.cubes.contains(p))
+            .map(|p| (p, 1))
+    }
+}
+
 /// Returns `true` if given point is contained withing given bounds.
 /// False otherwise
 fn in_bounds(p: &Point, bx: &(i32, i32), by: &(i32, i32), bz: &(i32, i32)) -> bool {
@@ -84,60 +106,20 @@ pub fn gold() {
 
     println!("Grid bounds: x={bx:?}, y={by:?}, z={bz:?}");
 
-    let mut total_free = 0;
-    // For each cube, count neighbours that don't exist
-    for cube in grid.iter() {
-        const NEARBY: [(i32, i32, i32); 6] = [
-            (-1,  0,  0),
-            ( 1,  0,  0),
-            ( 0, -1,  0),
-            ( 0,  1,  0),
-            ( 0,  0, -1),
-            ( 0,  0,  1),
-        ];
-
-
-        let free = NEARBY.iter().filter(|dir| {
-            let p = (cube.0 + dir.0, cube.1 + dir.1, cube.2 + dir.2);
-
-            if grid.contains(&p) { // Neighbour blocked, not visible
-                return false;
-            }
-
-            // Encountered air!
-            //
-            // Do a depth first search to see if we can reach boundary from this position
-            // If we can, ie. at some point we encounter a point that is NOT in bounds
-            // then, we count this face as visible.
-            let mut s = Vec::new();
-            let mut seen: HashSet<Point> = HashSet::new();
-
-            s.push(p);
-            while let Some(v) = s.pop() {
-                if !in_bounds(&v, &bx, &by, &bz) {
-                    // println!("Point {:?} NOT in bounds!", &v);
-                    return true;
-                }
-
-                if !seen.contains(&v) {
-                    seen.insert(v);
-                    // Push reachable neighbours
-                    s.extend(NEARBY.iter().filter_map(|d| {
-                        let p = (v.0 + d.0, v.1 + d.1, v.2 + d.2);
-                        if grid.contains(&p) {
-                            None
-                        } else {
-                            Some(p)
-                        }
-                    }));
-                }
-            }
-
-            return false; // Exhausted search space, this is a true pocket 
-        }).count();
+    // Flood the air around the droplet, starting from a corner just outside of it
+    let air = Air {
+        cubes: &grid,
+        bx: (bx.0 - 1, bx.1 + 1),
+        by: (by.0 - 1, by.1 + 1),
+        bz: (bz.0 - 1, bz.1 + 1),
+    };
+    let outside = search::bfs(&air, [(air.bx.0, air.by.0, air.bz.0)], |_| false);
 
-        total_free += free;
-    }
+    // Faces touching air that was reached from the outside are visible
+    let total_free = grid.iter()
+        .flat_map(|cube| NEARBY.iter().map(move |dir| (cube.0 + dir.0, cube.1 + dir.1, cube.2 + dir.2)))
+        .filter(|&p| outside.distance(p).is_some())
+        .count();
 
     println!("Gold: {total_free}");
 }
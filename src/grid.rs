@@ -0,0 +1,206 @@
+//! Row-major 2D grid shared by the grid based puzzles.
+//!
+//! Cells are addressed with `(col, row)`, `(0, 0)` being the top left corner.
+//! Directions are `(dcol, drow)` deltas, so north is `(0, -1)`.
+
+use std::ops::{Index, IndexMut};
+
+pub type Direction = (isize, isize);
+
+pub const NORTH: Direction = ( 0, -1);
+pub const SOUTH: Direction = ( 0,  1);
+pub const WEST: Direction  = (-1,  0);
+pub const EAST: Direction  = ( 1,  0);
+
+/// Orthogonal directions
+pub const DIRECTIONS4: [Direction; 4] = [NORTH, WEST, SOUTH, EAST];
+
+/// Orthogonal and diagonal directions
+pub const DIRECTIONS8: [Direction; 8] = [
+    (-1, -1), NORTH, ( 1, -1),
+    WEST,            EAST,
+    (-1,  1), SOUTH, ( 1,  1),
+];
+
+#[derive(Debug)]
+pub enum ParseError {
+    /// Character that the cell parser didn't accept
+    InvalidChar { col: usize, row: usize, c: char },
+    /// Row whose length differs from the first row
+    Ragged { row: usize, expected: usize, found: usize },
+    /// Map without any rows, or with rows that are all empty
+    Empty,
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::InvalidChar { col, row, c } => write!(f, "invalid character '{c}' at ({col}, {row})"),
+            ParseError::Ragged { row, expected, found } => write!(f, "row {row} has {found} cells, expected {expected}"),
+            ParseError::Empty => write!(f, "map has no cells"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid<T> {
+    cells: Vec<T>,
+    width: usize,
+    height: usize,
+}
+
+impl<T> Grid<T> {
+    /// Creates a grid from row-major cells.
+    /// Panics if number of cells doesn't match the dimensions.
+    pub fn new(width: usize, height: usize, cells: Vec<T>) -> Self {
+        assert_eq!(cells.len(), width * height, "cell count doesn't match {width}x{height}");
+        Self { cells, width, height }
+    }
+
+    /// Creates a grid with every cell set to `value`
+    pub fn filled(width: usize, height: usize, value: T) -> Self
+    where
+        T: Clone,
+    {
+        Self::new(width, height, vec![value; width * height])
+    }
+
+    /// Parses a character map, one row per line.
+    /// Each character is turned into a cell with `f`, which returns `None` for invalid characters.
+    /// Width is taken from the first line and every other line must be equally long.
+    /// Maps without any cells are rejected, so a parsed grid always has at least one.
+    pub fn parse(s: &str, mut f: impl FnMut(char) -> Option<T>) -> Result<Self, ParseError> {
+        let mut cells = Vec::new();
+        let mut width = 0;
+        let mut height = 0;
+
+        for (row, line) in s.lines().enumerate() {
+            let len = line.chars().count();
+            if row == 0 {
+                width = len;
+            } else if len != width {
+                return Err(ParseError::Ragged { row, expected: width, found: len });
+            }
+            height += 1;
+
+            for (col, c) in line.chars().enumerate() {
+                cells.push(f(c).ok_or(ParseError::InvalidChar { col, row, c })?);
+            }
+        }
+
+        if cells.is_empty() {
+            return Err(ParseError::Empty);
+        }
+
+        Ok(Self::new(width, height, cells))
+    }
+
+    pub fn width(&self) -> usize { self.width }
+
+    pub fn height(&self) -> usize { self.height }
+
+    pub fn len(&self) -> usize { self.cells.len() }
+
+    pub fn is_empty(&self) -> bool { self.cells.is_empty() }
+
+    /// Converts coordinate into an index of the underlying storage
+    pub fn index(&self, col: usize, row: usize) -> usize {
+        row * self.width + col
+    }
+
+    /// Converts index of the underlying storage into a coordinate
+    pub fn coord(&self, i: usize) -> (usize, usize) {
+        (i % self.width, i / self.width)
+    }
+
+    pub fn in_bounds(&self, col: usize, row: usize) -> bool {
+        col < self.width && row < self.height
+    }
+
+    pub fn get(&self, col: usize, row: usize) -> Option<&T> {
+        if self.in_bounds(col, row) {
+            Some(&self.cells[self.index(col, row)])
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, col: usize, row: usize) -> Option<&mut T> {
+        if self.in_bounds(col, row) {
+            let i = self.index(col, row);
+            Some(&mut self.cells[i])
+        } else {
+            None
+        }
+    }
+
+    /// Cells in row-major order
+    pub fn cells(&self) -> &[T] {
+        &self.cells
+    }
+
+    /// Cells of a single row, west to east
+    pub fn row(&self, row: usize) -> impl DoubleEndedIterator<Item = &T> {
+        self.cells[row * self.width..(row + 1) * self.width].iter()
+    }
+
+    /// Cells of a single column, north to south
+    pub fn column(&self, col: usize) -> impl DoubleEndedIterator<Item = &T> {
+        self.cells[col..].iter().step_by(self.width)
+    }
+
+    /// Coordinate one step away in given direction, if it's inside the grid
+    pub fn step(&self, col: usize, row: usize, dir: Direction) -> Option<(usize, usize)> {
+        step(self.width, self.height, (col, row), dir)
+    }
+
+    /// Coordinates from `(col, row)` towards `dir` until the edge of the grid.
+    /// Starting coordinate itself is not included.
+    ///
+    /// Like the neighbour iterators, this doesn't borrow the grid so cells can be modified while iterating.
+    pub fn ray(&self, col: usize, row: usize, dir: Direction) -> impl Iterator<Item = (usize, usize)> {
+        let (width, height) = (self.width, self.height);
+        std::iter::successors(self.step(col, row, dir), move |&p| step(width, height, p, dir))
+    }
+
+    /// Orthogonal neighbours that are inside the grid
+    pub fn neighbours4(&self, col: usize, row: usize) -> impl Iterator<Item = (usize, usize)> {
+        let (width, height) = (self.width, self.height);
+        DIRECTIONS4.into_iter().filter_map(move |dir| step(width, height, (col, row), dir))
+    }
+
+    /// Orthogonal and diagonal neighbours that are inside the grid
+    pub fn neighbours8(&self, col: usize, row: usize) -> impl Iterator<Item = (usize, usize)> {
+        let (width, height) = (self.width, self.height);
+        DIRECTIONS8.into_iter().filter_map(move |dir| step(width, height, (col, row), dir))
+    }
+
+    /// Creates a grid of the same size by applying `f` to every cell
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid::new(self.width, self.height, self.cells.iter().map(f).collect())
+    }
+}
+
+/// Moves `(col, row)` one step towards `dir`, staying within `width` x `height`
+fn step(width: usize, height: usize, (col, row): (usize, usize), dir: Direction) -> Option<(usize, usize)> {
+    let col = col.checked_add_signed(dir.0)?;
+    let row = row.checked_add_signed(dir.1)?;
+    (col < width && row < height).then_some((col, row))
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, (col, row): (usize, usize)) -> &T {
+        assert!(self.in_bounds(col, row), "({col}, {row}) out of bounds");
+        &self.cells[self.index(col, row)]
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, (col, row): (usize, usize)) -> &mut T {
+        assert!(self.in_bounds(col, row), "({col}, {row}) out of bounds");
+        let i = self.index(col, row);
+        &mut self.cells[i]
+    }
+}
@@ -1,5 +1,8 @@
 #![allow(dead_code)]
 
+mod grid;
+mod search;
+
 mod day01;
 mod day02;
 mod day03;
@@ -36,21 +39,41 @@ fn main() {
     // day06::silver_and_gold::<4>();
     // day06::silver_and_gold::<14>();
 
+    // day07::validate();
     // day07::silver_and_gold();
+    // day07::tree(day07::SortBy::Size);
+    // day07::du(1);
+    // day07::find("*.txt", 1000..);
+    // day07::largest(5);
+    // day07::plan_deletion(day07::Disk::default(), 3);
      
     // day08::silver();
     // day08::gold();
+    // day08::gold_threaded(4);
+    // day08::heatmap();
+    // day08::export("day08_", 8).unwrap();
      
-    // day09::silver_and_gold::<2>();
-    // day09::silver_and_gold::<10>();
+    // day09::silver_and_gold(2);
+    // day09::silver_and_gold(10);
+    // day09::per_knot(10);
+    // day09::render_trail(10);
      
     // day10::silver_and_gold();
+    // day10::answer_json();
+    // day10::render(day10::CrtConfig { width: 20, height: 12, sprite_width: 5 });
+    // day10::export(day10::CrtConfig::default(), "day10_").unwrap();
+    // day10::trace(1000);
+    // day10::debug();
      
-    // day11::silver_and_gold::<20, true>();
-    // day11::silver_and_gold::<10_000, false>();
+    // day11::silver_and_gold(20, day11::Relief::Divide(3));
+    // day11::silver_and_gold(10_000, day11::Relief::Modulo);
+    // day11::silver_and_gold_exact(20, day11::Relief::None);
+    // day11::long_run(1_000_000_000_000);
+    // day11::record(20, day11::Relief::Divide(3), "day11_").unwrap();
      
     // day12::silver();
     // day12::gold();
+    // day12::with_climb(2, 1);
      
     // day13::silver();
     // day13::gold();
@@ -0,0 +1,159 @@
+//! Graph searches shared by the puzzles.
+//!
+//! Anything implementing [`Graph`] can be searched with [`bfs`], [`dijkstra`] or [`astar`].
+//! Every search returns a [`SearchTree`] holding distances and parents of the reached nodes,
+//! which can be used to rebuild shortest paths.
+
+use std::cmp::Reverse;
+use std::collections::hash_map::Entry;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::hash::Hash;
+
+use crate::grid::Grid;
+
+pub trait Graph {
+    type Node: Copy + Eq + Hash + Ord;
+
+    /// Nodes reachable from `node` in a single step, paired with the cost of that step
+    fn neighbours(&self, node: Self::Node) -> impl Iterator<Item = (Self::Node, u64)>;
+}
+
+/// Result of a search
+#[derive(Debug, Clone)]
+pub struct SearchTree<N> {
+    /// Nodes the search started from
+    pub roots: Vec<N>,
+    /// First node accepted as a goal, `None` if the search ran out of nodes
+    pub goal: Option<N>,
+    distances: HashMap<N, u64>,
+    /// Each reached node except the roots has exactly one parent
+    parents: HashMap<N, N>,
+}
+
+impl<N: Copy + Eq + Hash> SearchTree<N> {
+    fn new(roots: Vec<N>) -> Self {
+        let distances = roots.iter().map(|&r| (r, 0)).collect();
+        Self { roots, goal: None, distances, parents: HashMap::new() }
+    }
+
+    /// Cost of the cheapest path from any root to `node`, `None` if it wasn't reached
+    pub fn distance(&self, node: N) -> Option<u64> {
+        self.distances.get(&node).copied()
+    }
+
+    pub fn parent(&self, node: N) -> Option<N> {
+        self.parents.get(&node).copied()
+    }
+
+    /// Every reached node with its distance, in no particular order
+    pub fn reached(&self) -> impl Iterator<Item = (N, u64)> + '_ {
+        self.distances.iter().map(|(&n, &d)| (n, d))
+    }
+
+    /// Shortest path from a root to `node`, both included. `None` if `node` wasn't reached.
+    pub fn path(&self, node: N) -> Option<Vec<N>> {
+        self.distances.get(&node)?;
+
+        let mut path: Vec<N> = std::iter::successors(Some(node), |&n| self.parent(n)).collect();
+        path.reverse();
+        Some(path)
+    }
+}
+
+/// Breadth-first search from every node in `roots`, ignoring step costs.
+/// Stops at the first node for which `is_goal` returns `true`.
+pub fn bfs<G: Graph>(graph: &G, roots: impl IntoIterator<Item = G::Node>, mut is_goal: impl FnMut(G::Node) -> bool) -> SearchTree<G::Node> {
+    let mut tree = SearchTree::new(roots.into_iter().collect());
+    let mut queue: VecDeque<G::Node> = tree.roots.iter().copied().collect();
+
+    while let Some(v) = queue.pop_front() {
+        if is_goal(v) {
+            tree.goal = Some(v);
+            break;
+        }
+
+        let distance = tree.distances[&v] + 1;
+        for (y, _) in graph.neighbours(v) {
+            if let Entry::Vacant(e) = tree.distances.entry(y) {
+                e.insert(distance);
+                tree.parents.insert(y, v);
+                queue.push_back(y);
+            }
+        }
+    }
+
+    tree
+}
+
+/// Cheapest paths from every node in `roots`.
+/// Stops once the cheapest node left is accepted by `is_goal`.
+pub fn dijkstra<G: Graph>(graph: &G, roots: impl IntoIterator<Item = G::Node>, is_goal: impl FnMut(G::Node) -> bool) -> SearchTree<G::Node> {
+    best_first(graph, roots, is_goal, |_| 0)
+}
+
+/// Cheapest path from `root` to `goal`, guided by `heuristic`.
+/// Heuristic must never overestimate the remaining cost, eg. [`manhattan`] when every step costs at least 1.
+pub fn astar<G: Graph>(graph: &G, root: G::Node, goal: G::Node, heuristic: impl Fn(G::Node) -> u64) -> SearchTree<G::Node> {
+    best_first(graph, [root], |v| v == goal, heuristic)
+}
+
+/// Dijkstra when `heuristic` is always zero, A* otherwise
+fn best_first<G: Graph>(
+    graph: &G,
+    roots: impl IntoIterator<Item = G::Node>,
+    mut is_goal: impl FnMut(G::Node) -> bool,
+    heuristic: impl Fn(G::Node) -> u64,
+) -> SearchTree<G::Node> {
+    let mut tree = SearchTree::new(roots.into_iter().collect());
+    // Ordered by estimated total cost, then cost so far
+    let mut heap: BinaryHeap<Reverse<(u64, u64, G::Node)>> = tree.roots.iter()
+        .map(|&r| Reverse((heuristic(r), 0, r)))
+        .collect();
+
+    while let Some(Reverse((_, distance, v))) = heap.pop() {
+        if distance > tree.distances[&v] {
+            continue; // Already found a cheaper way here
+        }
+
+        if is_goal(v) {
+            tree.goal = Some(v);
+            break;
+        }
+
+        for (y, cost) in graph.neighbours(v) {
+            let candidate = distance + cost;
+            if tree.distances.get(&y).is_none_or(|&d| candidate < d) {
+                tree.distances.insert(y, candidate);
+                tree.parents.insert(y, v);
+                heap.push(Reverse((candidate + heuristic(y), candidate, y)));
+            }
+        }
+    }
+
+    tree
+}
+
+/// Manhattan distance between two grid coordinates
+pub fn manhattan(a: (usize, usize), b: (usize, usize)) -> u64 {
+    (a.0.abs_diff(b.0) + a.1.abs_diff(b.1)) as u64
+}
+
+/// Orthogonal moves on a [`Grid`], `step` returns the cost of moving from one cell
+/// to a neighbouring one or `None` if that move isn't allowed
+pub struct GridGraph<'a, T, F> {
+    pub grid: &'a Grid<T>,
+    pub step: F,
+}
+
+impl<T, F> Graph for GridGraph<'_, T, F>
+where
+    F: Fn(&T, &T) -> Option<u64>,
+{
+    type Node = (usize, usize);
+
+    fn neighbours(&self, (col, row): (usize, usize)) -> impl Iterator<Item = ((usize, usize), u64)> {
+        let from = &self.grid[(col, row)];
+        self.grid.neighbours4(col, row)
+            .filter_map(move |p| (self.step)(from, &self.grid[p]).map(|cost| (p, cost)))
+    }
+}